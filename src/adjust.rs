@@ -0,0 +1,90 @@
+use std::{path::Path, str::FromStr};
+
+use material_colors::{blend::cam16_ucs, color::Argb, dynamic_color::Variant, hct::Hct};
+use serde::{Deserialize, Serialize};
+
+use crate::{loader, seed};
+
+/// Where `--blend` mixes the source color toward. Colors are used as they
+/// are, images get their own source color picked first.
+pub fn blend_target(
+    arg: &str,
+    load_options: &loader::Options,
+    seed_options: &seed::Options,
+) -> Result<Argb, String> {
+    if let Ok(color) = Argb::from_str(arg) {
+        return Ok(color);
+    }
+
+    let path = Path::new(arg);
+    if !path.is_file() {
+        return Err(format!("`{arg}` is neither a hex color nor an image"));
+    }
+
+    let pixels = loader::load_pixels(path, load_options).map_err(|err| format!("{arg}: {err}"))?;
+    let choice = seed::choose(&pixels.quantize(), &Variant::TonalSpot, seed_options);
+
+    Ok(choice.seed)
+}
+
+/// Mixes `source` toward `target` in CAM16-UCS, which keeps the mix
+/// perceptually even. A ratio of 0 keeps the source, 1 gives the target.
+pub fn blend(source: Argb, target: Argb, ratio: f64) -> Argb {
+    cam16_ucs(source, target, ratio)
+}
+
+// a ratio between 0 and 1
+pub fn parse_ratio(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+        _ => Err(format!("`{arg}` is not a ratio between 0 and 1")),
+    }
+}
+
+/// Changes made to the source color in HCT after it was picked, for when the
+/// image's color is almost right.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Adjustments {
+    /// Degrees added to the hue.
+    pub hue_shift: f64,
+    /// Factor the chroma gets multiplied with.
+    pub chroma_scale: f64,
+    /// Tone replacing the source color's tone.
+    pub tone: Option<f64>,
+}
+
+impl Adjustments {
+    pub fn apply(&self, color: Argb) -> Argb {
+        let hct = Hct::new(color);
+
+        let hue = (hct.get_hue() + self.hue_shift).rem_euclid(360.0);
+        let chroma = hct.get_chroma() * self.chroma_scale;
+        let tone = self.tone.unwrap_or(hct.get_tone());
+
+        Hct::from(hue, chroma, tone).into()
+    }
+}
+
+// a tone between 0 and 100
+pub fn parse_tone(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(tone) if (0.0..=100.0).contains(&tone) => Ok(tone),
+        _ => Err(format!("`{arg}` is not a tone between 0 and 100")),
+    }
+}
+
+// any finite number of degrees, negative ones rotate the other way
+pub fn parse_hue_shift(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(degrees) if degrees.is_finite() => Ok(degrees),
+        _ => Err(format!("`{arg}` is not a number of degrees")),
+    }
+}
+
+// any finite factor that isn't negative
+pub fn parse_chroma_scale(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(scale) if scale >= 0.0 && scale.is_finite() => Ok(scale),
+        _ => Err(format!("`{arg}` is not a factor of 0 or more")),
+    }
+}
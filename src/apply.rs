@@ -0,0 +1,86 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::Args;
+
+use crate::{OutputArgs, Outputs, history, metadata::SavedTheme, mode::Switch, share};
+
+#[derive(Args, Debug)]
+#[group(id = "source", required = true, multiple = false)]
+pub struct ApplyArgs {
+    /// A `theme.json` written by mat3, the last one is kept in
+    /// ~/.local/state/mat3
+    #[arg(long, value_name = "FILE", group = "source")]
+    from_theme: Option<PathBuf>,
+
+    /// The theme written N themes ago, as numbered by `mat3 history`
+    #[arg(long, value_name = "N", group = "source")]
+    history: Option<usize>,
+
+    /// A code printed by `mat3 share`
+    #[arg(long, value_name = "CODE", group = "source")]
+    code: Option<String>,
+
+    /// Scheme to write instead of the one the theme was last written with
+    #[arg(short, long, value_enum)]
+    scheme: Option<Switch>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+pub fn apply(arguments: ApplyArgs) {
+    let outputs = crate::check_outputs(arguments.output);
+
+    if let Some(n) = arguments.history {
+        history::apply_entry(n, arguments.scheme, &outputs);
+        return;
+    }
+
+    let saved = match (arguments.code, arguments.from_theme) {
+        (Some(code), _) => share::decode(&code),
+        (None, Some(path)) => read_theme(&path),
+        (None, None) => return,
+    };
+    let saved = match saved {
+        Ok(saved) => saved,
+        Err(err) => {
+            println!("{err}");
+            process::exit(1);
+        }
+    };
+
+    apply_saved(saved, arguments.scheme, &outputs);
+}
+
+pub fn read_theme(path: &Path) -> Result<SavedTheme, String> {
+    let json = fs::read_to_string(path)
+        .map_err(|err| format!("couldn't read {}: {err}", path.display()))?;
+
+    serde_json::from_str(&json).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+/// Rebuilds a saved theme from its recipe and writes all outputs, in the
+/// scheme it was saved with unless `scheme` says otherwise.
+pub fn apply_saved(saved: SavedTheme, scheme: Option<Switch>, outputs: &Outputs) {
+    let is_dark = match scheme {
+        Some(Switch::Light) => false,
+        Some(Switch::Dark) => true,
+        Some(Switch::Toggle) => !saved.is_dark,
+        None => saved.is_dark,
+    };
+
+    if saved.metadata.version != env!("CARGO_PKG_VERSION") {
+        println!(
+            "note: the theme was generated by mat3 {}, it might look slightly different now",
+            saved.metadata.version
+        );
+    }
+
+    let theme = saved.metadata.recipe.build();
+    crate::write_theme(&theme, &saved.metadata, is_dark, outputs);
+    crate::sigusr2_waybar();
+}
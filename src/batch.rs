@@ -0,0 +1,122 @@
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::Args;
+use material_colors::hct::Hct;
+use rayon::prelude::*;
+
+use crate::{Settings, ThemeArgs, metadata, seed, wallpapers};
+
+#[derive(Args, Debug)]
+pub struct BatchArgs {
+    /// Directory with the images to quantize
+    dir: PathBuf,
+
+    #[command(flatten)]
+    theme: ThemeArgs,
+}
+
+// one line of the summary
+struct Row {
+    image: String,
+    seed: String,
+    variant: String,
+    chroma: String,
+    notes: String,
+}
+
+/// Quantizes every image of a directory in parallel, filling the cache so
+/// switching to any of them later is instant, and prints what each one
+/// would give. No outputs are written.
+pub fn batch(arguments: BatchArgs) {
+    let mut settings = crate::check_args(arguments.theme);
+    if let Some(cache) = &mut settings.cache {
+        cache.defer_eviction();
+    }
+
+    let images = match wallpapers::list_images(&arguments.dir) {
+        Ok(images) => images,
+        Err(err) => {
+            println!("{err}");
+            process::exit(1);
+        }
+    };
+
+    let rows: Vec<Row> = images
+        .par_iter()
+        .map(|image| summarize(image, &settings))
+        .collect();
+
+    print_table(&rows);
+
+    if let Some(cache) = &settings.cache {
+        let evicted = cache.evict();
+        if images.len() > cache.max_entries() {
+            println!(
+                "note: the cache keeps {} images, not all {} of these stay cached, raise `cache_size` in the config to keep them",
+                cache.max_entries(),
+                images.len()
+            );
+        } else if evicted > 0 {
+            println!("removed {evicted} older images from the cache");
+        }
+    }
+}
+
+fn summarize(image: &Path, settings: &Settings) -> Row {
+    let name = image
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    match crate::quantize_images(&[(image.to_path_buf(), 1.0)], settings) {
+        Ok((quantized, _)) => {
+            let choice = seed::choose(&quantized, &settings.variant, &settings.seed_options);
+
+            Row {
+                image: name,
+                seed: choice.seed.to_hex_with_pound(),
+                variant: metadata::variant_to_name(&choice.variant).to_owned(),
+                chroma: format!("{:.1}", seed::mean(&quantized, Hct::get_chroma)),
+                notes: choice.warnings.join("; "),
+            }
+        }
+        Err(err) => Row {
+            image: name,
+            seed: "-".to_owned(),
+            variant: "-".to_owned(),
+            chroma: "-".to_owned(),
+            notes: err,
+        },
+    }
+}
+
+fn print_table(rows: &[Row]) {
+    let header = Row {
+        image: "IMAGE".to_owned(),
+        seed: "SEED".to_owned(),
+        variant: "VARIANT".to_owned(),
+        chroma: "CHROMA".to_owned(),
+        notes: "NOTES".to_owned(),
+    };
+
+    let width = |column: fn(&Row) -> &String| {
+        rows.iter()
+            .chain([&header])
+            .map(|row| column(row).chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let image_width = width(|row| &row.image);
+    let variant_width = width(|row| &row.variant);
+    let chroma_width = width(|row| &row.chroma);
+
+    for row in [&header].into_iter().chain(rows) {
+        println!(
+            "{:image_width$}  {:7}  {:variant_width$}  {:>chroma_width$}  {}",
+            row.image, row.seed, row.variant, row.chroma, row.notes,
+        );
+    }
+}
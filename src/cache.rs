@@ -0,0 +1,188 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use clap::{Args, ValueEnum};
+use material_colors::{color::Argb, quantize::QuantizerResult};
+
+use crate::{hash, loader, metadata::SourceImage};
+
+// how many images are kept when the config doesn't say otherwise
+pub const DEFAULT_MAX_ENTRIES: usize = 500;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum CacheAction {
+    /// Remove every cached image
+    Clear,
+    /// Show how many images are cached and how much space they take
+    Stats,
+}
+
+#[derive(Args, Debug)]
+pub struct CacheArgs {
+    #[arg(value_enum)]
+    action: CacheAction,
+}
+
+/// Quantized images by the hash of their contents, so switching back to an
+/// image skips decoding and quantizing it. Everything picked from the
+/// quantized colors afterwards is cheap and stays uncached, changing those
+/// options doesn't need a new entry.
+pub struct Cache {
+    dir: PathBuf,
+    max_entries: usize,
+    // set while filling the cache with many images at once
+    defer_eviction: bool,
+}
+
+impl Cache {
+    pub fn new(max_entries: usize) -> Cache {
+        Cache {
+            dir: cache_dir(),
+            max_entries,
+            defer_eviction: false,
+        }
+    }
+
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Stops `insert` from evicting, so images inserted in parallel can't
+    /// evict each other. `evict` has to be called once they are all in.
+    pub fn defer_eviction(&mut self) {
+        self.defer_eviction = true;
+    }
+
+    /// `key` comes from `key()`. A hit counts as a use, so images that keep
+    /// getting used stay cached.
+    pub fn get(&self, key: &str) -> Option<QuantizerResult> {
+        let path = self.dir.join(key.to_owned() + ".json");
+        let json = fs::read_to_string(&path).ok()?;
+        let counts: Vec<(u32, u32)> = serde_json::from_str(&json).ok()?;
+
+        // eviction goes by the modification time
+        let _ = File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+
+        Some(QuantizerResult {
+            color_to_count: counts
+                .into_iter()
+                .map(|(color, count)| (Argb::from_u32(color), count))
+                .collect(),
+            // only used while quantizing
+            input_pixel_to_cluster_pixel: Default::default(),
+        })
+    }
+
+    pub fn insert(&self, key: &str, result: &QuantizerResult) {
+        let counts: Vec<(u32, u32)> = result
+            .color_to_count
+            .iter()
+            .map(|(color, count)| (to_u32(color), *count))
+            .collect();
+
+        // a cache that can't be written only makes things slower
+        if fs::create_dir_all(&self.dir).is_err() {
+            return;
+        }
+        let json = serde_json::to_string(&counts).unwrap();
+        let _ = fs::write(self.dir.join(key.to_owned() + ".json"), json);
+
+        if !self.defer_eviction {
+            self.evict();
+        }
+    }
+
+    /// Removes the least recently used entries above `max_entries`,
+    /// returns how many were removed.
+    pub fn evict(&self) -> usize {
+        let mut entries = entries(&self.dir);
+        if entries.len() <= self.max_entries {
+            return 0;
+        }
+
+        entries.sort_by_key(|(_, modified, _)| *modified);
+        let excess = entries.len() - self.max_entries;
+        for (path, _, _) in entries.into_iter().take(excess) {
+            let _ = fs::remove_file(path);
+        }
+        excess
+    }
+}
+
+/// Cache key of `images` with their weights, loaded with `load_options`,
+/// which is everything that changes the quantized colors. The mat3 version
+/// is part of it since sampling might change between versions. Only the
+/// ratio of the weights matters, like for merging the images.
+pub fn key(images: &[SourceImage], load_options: &loader::Options) -> String {
+    let max_weight = images.iter().map(|image| image.weight).fold(0.0, f64::max);
+
+    let mut key_string = env!("CARGO_PKG_VERSION").to_owned() + ";";
+    for image in images {
+        key_string += &format!("{}:{};", image.hash, image.weight / max_weight);
+    }
+    key_string += &format!(
+        "{}:{:?}:{:?}",
+        load_options.svg_size,
+        load_options.svg_background.map(|color| color.to_hex()),
+        load_options.frame,
+    );
+
+    format!("{:016x}", hash::fnv1a(key_string.as_bytes()))
+}
+
+/// `$XDG_CACHE_HOME/mat3` or `~/.cache/mat3`.
+pub fn cache_dir() -> PathBuf {
+    crate::xdg_dir("XDG_CACHE_HOME", ".cache").join("mat3")
+}
+
+pub fn cache(arguments: CacheArgs) {
+    let dir = cache_dir();
+    let entries = entries(&dir);
+
+    match arguments.action {
+        CacheAction::Clear => {
+            for (path, _, _) in &entries {
+                let _ = fs::remove_file(path);
+            }
+            println!("removed {} cached images", entries.len());
+        }
+        CacheAction::Stats => {
+            let size: u64 = entries.iter().map(|(_, _, size)| size).sum();
+            println!("{}", dir.display());
+            println!(
+                "{} cached images, {:.1} KiB",
+                entries.len(),
+                size as f64 / 1024.0
+            );
+        }
+    }
+}
+
+// every cache file with when it was last used and its size
+fn entries(dir: &Path) -> Vec<(PathBuf, SystemTime, u64)> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    read_dir
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.path(), metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+fn to_u32(color: &Argb) -> u32 {
+    u32::from(color.alpha) << 24
+        | u32::from(color.red) << 16
+        | u32::from(color.green) << 8
+        | u32::from(color.blue)
+}
@@ -0,0 +1,107 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use material_colors::color::Argb;
+use serde::Deserialize;
+
+use crate::{
+    custom::CustomEntry, mode::SchemeMode, overrides::KeyColors, schedule::ScheduleConfig,
+    seed::LowChroma,
+};
+
+/// Contents of `config.toml`. Everything is optional, command line flags
+/// take precedence over the values in here.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// `"light"`, `"dark"`, `"auto"` or `"schedule"`.
+    pub scheme: Option<SchemeMode>,
+    /// Average image tone from which `scheme = "auto"` picks light.
+    pub auto_threshold: Option<f64>,
+    /// `[schedule]`, when `scheme = "schedule"` switches to light and dark.
+    pub schedule: ScheduleConfig,
+    /// What to do when the image has hardly any color.
+    pub low_chroma: Option<LowChroma>,
+    /// Average chroma below which an image counts as grayscale.
+    pub low_chroma_threshold: Option<f64>,
+    /// Source color used instead of the image with `low_chroma = "fallback"`.
+    #[serde(with = "crate::hex::option")]
+    pub fallback_seed: Option<Argb>,
+    /// Hue ranges in degrees, like `[[10, 50]]`, no source color is picked
+    /// from. Ranges may wrap around, `[340, 20]` covers the reds.
+    pub exclude_hues: Vec<[f64; 2]>,
+    /// Hue ranges a source color is picked from whenever the image has one.
+    pub prefer_hues: Vec<[f64; 2]>,
+    /// Colors with less chroma than this are never picked as source color.
+    pub min_chroma: Option<f64>,
+    /// From -1 (reduced) to 1 (high), 0 is the standard contrast.
+    pub contrast: Option<f64>,
+    /// How many quantized images the cache keeps.
+    pub cache_size: Option<usize>,
+    /// `[key_colors]`, fixed colors for some of the palettes.
+    pub key_colors: KeyColors,
+    /// `[custom_colors]`, extra named colors like `brand = "#e5322d"`.
+    pub custom_colors: BTreeMap<String, CustomEntry>,
+    /// How many colors of the image to add as `accent1`..`accentN`.
+    pub accents: Option<usize>,
+}
+
+/// `$XDG_CONFIG_HOME/mat3/config.toml` or `~/.config/mat3/config.toml`.
+pub fn default_path() -> PathBuf {
+    crate::xdg_dir("XDG_CONFIG_HOME", ".config").join("mat3/config.toml")
+}
+
+/// Reads the config at `path`. A missing file is the same as an empty one,
+/// a broken one is an error.
+pub fn load(path: &Path) -> Result<Config, String> {
+    let toml_string = match fs::read_to_string(path) {
+        Ok(toml_string) => toml_string,
+        Err(_) if !path.exists() => return Ok(Config::default()),
+        Err(err) => return Err(format!("couldn't read {}: {err}", path.display())),
+    };
+
+    let config: Config =
+        toml::from_str(&toml_string).map_err(|err| format!("{}: {err}", path.display()))?;
+    check(&config).map_err(|err| format!("{}: {err}", path.display()))?;
+
+    Ok(config)
+}
+
+// TOML allows `nan` and `inf`, the numbers get checked like the flags
+fn check(config: &Config) -> Result<(), String> {
+    let ranges = [
+        ("auto_threshold", config.auto_threshold, 0.0, 100.0),
+        (
+            "low_chroma_threshold",
+            config.low_chroma_threshold,
+            0.0,
+            f64::MAX,
+        ),
+        ("min_chroma", config.min_chroma, 0.0, f64::MAX),
+        ("contrast", config.contrast, -1.0, 1.0),
+    ];
+
+    for (name, value, min, max) in ranges {
+        if let Some(value) = value
+            && !(min..=max).contains(&value)
+        {
+            return Err(format!("`{name}` can't be {value}"));
+        }
+    }
+
+    for (name, ranges) in [
+        ("exclude_hues", &config.exclude_hues),
+        ("prefer_hues", &config.prefer_hues),
+    ] {
+        for range in ranges {
+            if !range.iter().all(|hue| (0.0..=360.0).contains(hue)) {
+                return Err(format!("`{name}` can't be {range:?}"));
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use std::{fs::File, io::Write, path::Path};
+
+use material_colors::{color::Argb, scheme::Scheme, theme::Theme};
+
+use crate::{custom, layers};
+
+/// Tones `--palette-tones` exports when no list is given, the ones Material
+/// Design uses for its roles.
+pub const STANDARD_TONES: [u8; 27] = [
+    0, 4, 5, 6, 10, 12, 17, 20, 22, 24, 25, 30, 35, 40, 50, 60, 70, 80, 87, 90, 92, 94, 95, 96, 98,
+    99, 100,
+];
+
+pub fn create_css(
+    theme: &Theme,
+    is_dark: bool,
+    both_schemes: bool,
+    palette_tones: &[u8],
+    config_file_path: &str,
+) {
+    let css_string = theme_to_css(theme, is_dark, both_schemes, palette_tones).unwrap();
+    let mut css_file = File::create(config_file_path).unwrap();
+    css_file.write_all(css_string.as_bytes()).unwrap();
+}
+
+/// Writes the scheme chosen by `is_dark` to `config_file_path`, and with
+/// `both_schemes` also `colors-light.css` and `colors-dark.css` next to it,
+/// since GTK CSS has no media queries to switch between them.
+pub fn create_waybar_css(
+    theme: &Theme,
+    is_dark: bool,
+    both_schemes: bool,
+    palette_tones: &[u8],
+    config_file_path: &str,
+) {
+    let css_string = theme_to_waybar_css(theme, is_dark, palette_tones).unwrap();
+    let mut css_file = File::create(config_file_path).unwrap();
+    css_file.write_all(css_string.as_bytes()).unwrap();
+
+    if both_schemes {
+        let dir = Path::new(config_file_path)
+            .parent()
+            .unwrap_or(Path::new("."));
+
+        for (name, is_dark) in [("colors-light.css", false), ("colors-dark.css", true)] {
+            let css_string = theme_to_waybar_css(theme, is_dark, palette_tones).unwrap();
+            let mut css_file = File::create(dir.join(name)).unwrap();
+            css_file.write_all(css_string.as_bytes()).unwrap();
+        }
+    }
+}
+
+/// Every color of the light or dark scheme of `theme`, with the names used
+/// in the outputs. State layers and elevations follow the scheme roles,
+/// custom colors come last.
+pub fn roles(theme: &Theme, is_dark: bool) -> Vec<(String, Argb)> {
+    let scheme = if is_dark {
+        &theme.schemes.dark
+    } else {
+        &theme.schemes.light
+    };
+
+    let mut roles: Vec<(String, Argb)> = scheme_roles(scheme)
+        .into_iter()
+        .map(|(name, color)| (name.to_owned(), color))
+        .collect();
+    roles.extend(layers::roles(scheme));
+    roles.extend(custom::roles(theme, is_dark));
+
+    roles
+}
+
+/// `tones` of each palette of `theme`, named like `primary_40`. They're
+/// the same in both schemes.
+pub fn palette_tones(theme: &Theme, tones: &[u8]) -> Vec<(String, Argb)> {
+    let palettes = &theme.palettes;
+    let mut colors = Vec::new();
+
+    for (name, palette) in [
+        ("primary", &palettes.primary),
+        ("secondary", &palettes.secondary),
+        ("tertiary", &palettes.tertiary),
+        ("neutral", &palettes.neutral),
+        ("neutral_variant", &palettes.neutral_variant),
+        ("error", &palettes.error),
+    ] {
+        for tone in tones {
+            colors.push((format!("{name}_{tone}"), palette.tone(i32::from(*tone))));
+        }
+    }
+
+    colors
+}
+
+// same names and order as `Scheme`'s `IntoIterator`, which needs an owned
+// scheme
+fn scheme_roles(scheme: &Scheme) -> [(&'static str, Argb); 49] {
+    [
+        ("primary", scheme.primary),
+        ("on_primary", scheme.on_primary),
+        ("primary_container", scheme.primary_container),
+        ("on_primary_container", scheme.on_primary_container),
+        ("inverse_primary", scheme.inverse_primary),
+        ("primary_fixed", scheme.primary_fixed),
+        ("primary_fixed_dim", scheme.primary_fixed_dim),
+        ("on_primary_fixed", scheme.on_primary_fixed),
+        ("on_primary_fixed_variant", scheme.on_primary_fixed_variant),
+        ("secondary", scheme.secondary),
+        ("on_secondary", scheme.on_secondary),
+        ("secondary_container", scheme.secondary_container),
+        ("on_secondary_container", scheme.on_secondary_container),
+        ("secondary_fixed", scheme.secondary_fixed),
+        ("secondary_fixed_dim", scheme.secondary_fixed_dim),
+        ("on_secondary_fixed", scheme.on_secondary_fixed),
+        (
+            "on_secondary_fixed_variant",
+            scheme.on_secondary_fixed_variant,
+        ),
+        ("tertiary", scheme.tertiary),
+        ("on_tertiary", scheme.on_tertiary),
+        ("tertiary_container", scheme.tertiary_container),
+        ("on_tertiary_container", scheme.on_tertiary_container),
+        ("tertiary_fixed", scheme.tertiary_fixed),
+        ("tertiary_fixed_dim", scheme.tertiary_fixed_dim),
+        ("on_tertiary_fixed", scheme.on_tertiary_fixed),
+        (
+            "on_tertiary_fixed_variant",
+            scheme.on_tertiary_fixed_variant,
+        ),
+        ("error", scheme.error),
+        ("on_error", scheme.on_error),
+        ("error_container", scheme.error_container),
+        ("on_error_container", scheme.on_error_container),
+        ("surface_dim", scheme.surface_dim),
+        ("surface", scheme.surface),
+        ("surface_tint", scheme.surface_tint),
+        ("surface_bright", scheme.surface_bright),
+        ("surface_container_lowest", scheme.surface_container_lowest),
+        ("surface_container_low", scheme.surface_container_low),
+        ("surface_container", scheme.surface_container),
+        ("surface_container_high", scheme.surface_container_high),
+        (
+            "surface_container_highest",
+            scheme.surface_container_highest,
+        ),
+        ("on_surface", scheme.on_surface),
+        ("on_surface_variant", scheme.on_surface_variant),
+        ("outline", scheme.outline),
+        ("outline_variant", scheme.outline_variant),
+        ("inverse_surface", scheme.inverse_surface),
+        ("inverse_on_surface", scheme.inverse_on_surface),
+        ("surface_variant", scheme.surface_variant),
+        ("background", scheme.background),
+        ("on_background", scheme.on_background),
+        ("shadow", scheme.shadow),
+        ("scrim", scheme.scrim),
+    ]
+}
+
+// With `both_schemes` the chosen scheme stays on `:root`, pages can pick one
+// with `data-theme="light"`/`"dark"`, or follow the system with
+// `data-theme="system"`. Palette tones don't change with the scheme and only
+// go on `:root`.
+fn theme_to_css(
+    theme: &Theme,
+    is_dark: bool,
+    both_schemes: bool,
+    palette_tones: &[u8],
+) -> Result<String, ()> {
+    let mut css_buf = String::new();
+
+    let mut root = roles(theme, is_dark);
+    root.extend(self::palette_tones(theme, palette_tones));
+
+    css_buf += &("# Source: ".to_owned() + &theme.source.to_hex() + "\n");
+    css_buf += &css_block(":root", &root, "");
+
+    if both_schemes {
+        for (name, is_dark) in [("light", false), ("dark", true)] {
+            let roles = roles(theme, is_dark);
+            let selector = format!("[data-theme=\"{name}\"]");
+
+            css_buf += "\n";
+            css_buf += &css_block(&selector, &roles, "");
+            css_buf += &format!("\n@media (prefers-color-scheme: {name}) {{\n");
+            css_buf += &css_block("[data-theme=\"system\"]", &roles, "  ");
+            css_buf += "\n}";
+        }
+    }
+
+    Ok(css_buf)
+}
+
+fn css_block(selector: &str, roles: &[(String, Argb)], indent: &str) -> String {
+    let mut css_buf = String::new();
+
+    css_buf += &(indent.to_owned() + selector + " {\n");
+    for (name, color) in roles {
+        css_buf += &(indent.to_owned() + "  --" + name + ": #" + &color.to_hex() + ";\n");
+    }
+    css_buf += &(indent.to_owned() + "}");
+
+    css_buf
+}
+
+fn theme_to_waybar_css(theme: &Theme, is_dark: bool, palette_tones: &[u8]) -> Result<String, ()> {
+    let mut css_buf = String::new();
+
+    let mut colors = roles(theme, is_dark);
+    colors.extend(self::palette_tones(theme, palette_tones));
+    for (name, color) in colors {
+        css_buf += &("@define-color ".to_owned() + &name + " #" + &color.to_hex() + ";\n");
+    }
+
+    Ok(css_buf)
+}
@@ -0,0 +1,167 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    str::FromStr,
+};
+
+use material_colors::{
+    color::Argb,
+    theme::{CustomColor, Theme, ThemeBuilder},
+};
+use serde::{Deserialize, Serialize};
+
+/// A named color added to the theme next to the palette colors, like a
+/// brand color or the color of a warning.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Custom {
+    pub name: String,
+    #[serde(with = "crate::hex")]
+    pub value: Argb,
+    /// Shift the hue toward the source color so it fits the theme.
+    pub blend: bool,
+}
+
+impl Custom {
+    pub fn to_custom_color(&self) -> CustomColor {
+        CustomColor {
+            value: self.value,
+            name: self.name.clone(),
+            blend: self.blend,
+        }
+    }
+}
+
+/// `[custom_colors]` in the config, either `name = "#hex"` or
+/// `name = { color = "#hex", blend = false }`.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum CustomEntry {
+    Color(String),
+    Table {
+        color: String,
+        #[serde(default = "blend_default")]
+        blend: bool,
+    },
+}
+
+fn blend_default() -> bool {
+    true
+}
+
+/// Turns the config section into custom colors, sorted by name.
+pub fn from_config(entries: BTreeMap<String, CustomEntry>) -> Result<Vec<Custom>, String> {
+    entries
+        .into_iter()
+        .map(|(name, entry)| {
+            let (color, blend) = match entry {
+                CustomEntry::Color(color) => (color, true),
+                CustomEntry::Table { color, blend } => (color, blend),
+            };
+            custom(&name, &color, blend)
+        })
+        .collect()
+}
+
+// `name=#hex`, `name=#hex,blend` or `name=#hex,noblend`, blending is the default
+pub fn parse_custom_arg(arg: &str) -> Result<Custom, String> {
+    let Some((name, color)) = arg.split_once('=') else {
+        return Err(format!(
+            "`{arg}` should look like name=#hex[,blend|,noblend]"
+        ));
+    };
+
+    let (color, blend) = match color.split_once(',') {
+        Some((color, "blend")) => (color, true),
+        Some((color, "noblend")) => (color, false),
+        Some((_, other)) => return Err(format!("`{other}` is neither `blend` nor `noblend`")),
+        None => (color, true),
+    };
+
+    custom(name, color, blend)
+}
+
+fn custom(name: &str, color: &str, blend: bool) -> Result<Custom, String> {
+    check_name(name)?;
+
+    let value =
+        Argb::from_str(color).map_err(|_| format!("`{color}` is not a hex color like #4285f4"))?;
+
+    Ok(Custom {
+        name: name.to_owned(),
+        value,
+        blend,
+    })
+}
+
+/// Custom color names end up in CSS variable names and GTK defines, so only
+/// letters, digits, `_` and `-` are allowed.
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '-')
+    {
+        return Err(format!(
+            "`{name}` can't be used as name of a custom color, only letters, digits, `_` and `-` are allowed"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Fails if one of the roles of a custom color would get the name of
+/// another color in the outputs, which would silently replace it. Besides
+/// the scheme roles, state layers, elevations and palette tones, that's
+/// the first `accents` accents and the other custom colors.
+pub fn check_clashes(custom_colors: &[Custom], accents: usize) -> Result<(), String> {
+    // the names don't depend on the colors
+    let theme = ThemeBuilder::with_source(Argb::new(255, 66, 133, 244)).build();
+    let all_tones: Vec<u8> = (0..=100).collect();
+
+    let mut taken: HashSet<String> = crate::css::roles(&theme, false)
+        .into_iter()
+        .chain(crate::css::palette_tones(&theme, &all_tones))
+        .map(|(name, _)| name)
+        .collect();
+    taken.extend((1..=accents).flat_map(|i| role_names(&format!("accent{i}"))));
+
+    for custom in custom_colors {
+        for role in role_names(&custom.name) {
+            if !taken.insert(role.clone()) {
+                return Err(format!(
+                    "the custom color `{}` would replace `{role}`, pick another name",
+                    custom.name
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The four roles of every custom color in `theme` as (name, color) pairs,
+/// for the exporters.
+pub fn roles(theme: &Theme, is_dark: bool) -> Vec<(String, Argb)> {
+    let mut roles = Vec::new();
+
+    for group in &theme.custom_colors {
+        let colors = if is_dark { &group.dark } else { &group.light };
+        let [color, on_color, container, on_container] = role_names(&group.color.name);
+
+        roles.push((color, colors.color));
+        roles.push((on_color, colors.on_color));
+        roles.push((container, colors.color_container));
+        roles.push((on_container, colors.on_color_container));
+    }
+
+    roles
+}
+
+// in the order `roles` returns them
+fn role_names(name: &str) -> [String; 4] {
+    [
+        name.to_owned(),
+        format!("on_{name}"),
+        format!("{name}_container"),
+        format!("on_{name}_container"),
+    ]
+}
@@ -0,0 +1,46 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// bytes read from a file at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// FNV-1a, unlike std's hashers it is guaranteed to stay the same between
+/// Rust versions, so it can be used for anything that is saved.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    update(FNV_OFFSET, bytes)
+}
+
+fn update(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Hash of the contents of the file at `path` as hex, the same image under
+/// another name gets the same hash. The file is read in chunks, so large
+/// images don't have to fit in memory twice.
+pub fn file_hash(path: &Path) -> Result<String, String> {
+    let error = |err| format!("couldn't read {}: {err}", path.display());
+
+    let mut reader = BufReader::new(File::open(path).map_err(error)?);
+    let mut chunk = vec![0; CHUNK_SIZE];
+    let mut hash = FNV_OFFSET;
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => hash = update(hash, &chunk[..read]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(error(err)),
+        }
+    }
+
+    Ok(format!("{hash:016x}"))
+}
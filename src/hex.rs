@@ -0,0 +1,47 @@
+//! Colors as hex strings like `#4285f4` in the config and the saved files,
+//! for `#[serde(with = "crate::hex")]`.
+
+use std::str::FromStr;
+
+use material_colors::color::Argb;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+pub fn serialize<S>(color: &Argb, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&color.to_hex_with_pound())
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Argb, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let color_string = String::deserialize(deserializer)?;
+
+    Argb::from_str(&color_string)
+        .map_err(|_| D::Error::custom(format!("`{color_string}` is not a hex color")))
+}
+
+/// The same for optional colors, missing ones are left out.
+pub mod option {
+    use material_colors::color::Argb;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(color: &Option<Argb>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match color {
+            Some(color) => super::serialize(color, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Argb>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        super::deserialize(deserializer).map(Some)
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OutputArgs, Outputs, apply, metadata, mode::Switch, state};
+
+// one json object per line, oldest first
+const HISTORY_FILE: &str = "history.jsonl";
+// entries kept, older ones get dropped
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    /// Seconds since the epoch.
+    timestamp: u64,
+    #[serde(flatten)]
+    theme: metadata::SavedTheme,
+}
+
+/// Appends a written theme to the history.
+pub fn record(metadata: &metadata::Metadata, is_dark: bool) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let entry = Entry {
+        timestamp,
+        theme: metadata::SavedTheme {
+            metadata: metadata.clone(),
+            is_dark,
+        },
+    };
+
+    let path = state::state_dir().join(HISTORY_FILE);
+    let line = serde_json::to_string(&entry).unwrap() + "\n";
+    let appended = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if appended.is_err() {
+        println!("couldn't write to {}", path.display());
+        return;
+    }
+
+    // only rewrite the file once it's well over the limit
+    let mut entries = read();
+    if entries.len() > MAX_ENTRIES + MAX_ENTRIES / 10 {
+        entries.drain(..entries.len() - MAX_ENTRIES);
+        write(&entries);
+    }
+}
+
+/// Prints the history newest first, numbered like `apply --history` takes
+/// them.
+pub fn show() {
+    let entries = read();
+
+    for (n, entry) in entries.iter().rev().enumerate() {
+        let metadata = &entry.theme.metadata;
+        let images: Vec<String> = metadata
+            .images
+            .iter()
+            .filter_map(|image| image.path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+
+        print!(
+            "{n:>3}  {}  {:11} {:5}  {:20}",
+            format_time(entry.timestamp),
+            metadata::variant_to_name(&metadata.recipe.variant),
+            if entry.theme.is_dark { "dark" } else { "light" },
+            images.join(", "),
+        );
+        crate::print_color(metadata.recipe.source);
+    }
+}
+
+/// Goes back to the theme written before the last one.
+pub fn undo(arguments: OutputArgs) {
+    let outputs = crate::check_outputs(arguments);
+    let mut entries = read();
+
+    if entries.len() < 2 {
+        println!("there is nothing to undo");
+        process::exit(1);
+    }
+
+    // applying it records it again
+    entries.pop();
+    let previous = entries.pop().unwrap();
+    write(&entries);
+
+    apply::apply_saved(previous.theme, None, &outputs);
+}
+
+/// Writes the outputs of the entry `n` steps back, 0 being the current one.
+pub fn apply_entry(n: usize, scheme: Option<Switch>, outputs: &Outputs) {
+    let mut entries = read();
+
+    if n >= entries.len() {
+        println!("the history only has {} entries", entries.len());
+        process::exit(1);
+    }
+
+    let entry = entries.remove(entries.len() - 1 - n);
+    apply::apply_saved(entry.theme, scheme, outputs);
+}
+
+// broken lines are skipped, one bad write shouldn't lose the whole history
+fn read() -> Vec<Entry> {
+    let Ok(history) = fs::read_to_string(state::state_dir().join(HISTORY_FILE)) else {
+        return Vec::new();
+    };
+
+    history
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn write(entries: &[Entry]) {
+    let mut history = String::new();
+    for entry in entries {
+        history += &(serde_json::to_string(entry).unwrap() + "\n");
+    }
+
+    state::write_file(HISTORY_FILE, &history);
+}
+
+// `YYYY-MM-DD HH:MM` in local time
+fn format_time(timestamp: u64) -> String {
+    // SAFETY: `localtime_r` only writes to the `tm` it's given
+    let tm = unsafe {
+        let time = timestamp as libc::time_t;
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&time, &mut tm);
+        tm
+    };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min
+    )
+}
@@ -0,0 +1,86 @@
+use material_colors::{color::Argb, scheme::Scheme};
+
+// opacity of the content color over the container for each state, focus was
+// 12% in the first version of Material 3
+const STATES: [(&str, f64); 4] = [
+    ("hover", 0.08),
+    ("focus", 0.10),
+    ("pressed", 0.12),
+    ("dragged", 0.16),
+];
+
+// opacity of the surface tint over the surface for elevation levels 0 to 5
+const ELEVATIONS: [f64; 6] = [0.0, 0.05, 0.08, 0.11, 0.12, 0.14];
+
+/// State layers of the colors that have content colors, like
+/// `primary_hover`, and the surface at each elevation level, like
+/// `surface_elevation_2`. Both are flattened to opaque colors since not
+/// every output can blend.
+pub fn roles(scheme: &Scheme) -> Vec<(String, Argb)> {
+    let mut roles = Vec::new();
+
+    for (name, color, on_color) in [
+        ("primary", scheme.primary, scheme.on_primary),
+        (
+            "primary_container",
+            scheme.primary_container,
+            scheme.on_primary_container,
+        ),
+        ("secondary", scheme.secondary, scheme.on_secondary),
+        (
+            "secondary_container",
+            scheme.secondary_container,
+            scheme.on_secondary_container,
+        ),
+        ("tertiary", scheme.tertiary, scheme.on_tertiary),
+        (
+            "tertiary_container",
+            scheme.tertiary_container,
+            scheme.on_tertiary_container,
+        ),
+        ("error", scheme.error, scheme.on_error),
+        (
+            "error_container",
+            scheme.error_container,
+            scheme.on_error_container,
+        ),
+        ("surface", scheme.surface, scheme.on_surface),
+        (
+            "surface_variant",
+            scheme.surface_variant,
+            scheme.on_surface_variant,
+        ),
+        (
+            "inverse_surface",
+            scheme.inverse_surface,
+            scheme.inverse_on_surface,
+        ),
+    ] {
+        for (state, opacity) in STATES {
+            roles.push((format!("{name}_{state}"), overlay(color, on_color, opacity)));
+        }
+    }
+
+    for (level, opacity) in ELEVATIONS.into_iter().enumerate() {
+        roles.push((
+            format!("surface_elevation_{level}"),
+            overlay(scheme.surface, scheme.surface_tint, opacity),
+        ));
+    }
+
+    roles
+}
+
+// `top` drawn over `bottom` at `opacity`
+fn overlay(bottom: Argb, top: Argb, opacity: f64) -> Argb {
+    let mix = |bottom: u8, top: u8| {
+        (f64::from(bottom) * (1.0 - opacity) + f64::from(top) * opacity).round() as u8
+    };
+
+    Argb::new(
+        255,
+        mix(bottom.red, top.red),
+        mix(bottom.green, top.green),
+        mix(bottom.blue, top.blue),
+    )
+}
@@ -0,0 +1,464 @@
+use std::{fs::File, io::BufReader, path::Path, process::Command};
+
+use image::{
+    AnimationDecoder, DynamicImage, Frames, ImageReader, Rgba, Rgba32FImage, RgbaImage,
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    imageops::{self, FilterType},
+};
+use material_colors::{
+    color::Argb,
+    quantize::{Quantizer, QuantizerCelebi, QuantizerResult},
+};
+
+// images get scaled down to this size before quantizing
+const SAMPLE_SIZE: u32 = 128;
+// pixels more transparent than this don't count at all
+const MIN_ALPHA: u8 = 16;
+// how many frames of an animation or video get merged
+const SAMPLE_FRAMES: usize = 8;
+// total weight of an image with weight 1 after merging
+const MERGE_SCALE: f64 = (1 << 20) as f64;
+
+/// Settings for turning files into pixels.
+pub struct Options {
+    /// Width and height SVG images get rasterized at.
+    pub svg_size: u32,
+    /// Color drawn behind transparent areas of SVG images, if any.
+    pub svg_background: Option<Argb>,
+    /// Only use this frame of an animation or video instead of sampling
+    /// `SAMPLE_FRAMES` of them.
+    pub frame: Option<usize>,
+}
+
+/// Pixels of one or more images, each one weighted by how much it should
+/// count when picking the source color.
+pub struct Pixels {
+    colors: Vec<Argb>,
+    weights: Vec<u32>,
+}
+
+impl Pixels {
+    pub fn new() -> Pixels {
+        Pixels {
+            colors: Vec::new(),
+            weights: Vec::new(),
+        }
+    }
+
+    /// Adds every pixel of `image`, weighted by its alpha. Pixels below
+    /// `MIN_ALPHA` are dropped so transparent areas (which are usually
+    /// black) don't end up in the quantizer.
+    pub fn add_image(&mut self, image: &RgbaImage) {
+        for pixel in image.pixels() {
+            let [red, green, blue, alpha] = pixel.0;
+
+            if alpha < MIN_ALPHA {
+                continue;
+            }
+
+            self.colors.push(Argb::new(255, red, green, blue));
+            self.weights.push(u32::from(alpha));
+        }
+    }
+
+    /// Pools the pixels of another image into these ones. No matter how
+    /// many pixels `other` has, it counts `weight` times as much as an image
+    /// merged with weight 1.
+    pub fn merge(&mut self, other: Pixels, weight: f64) {
+        let total: f64 = other.weights.iter().map(|weight| f64::from(*weight)).sum();
+        let scale = weight * MERGE_SCALE / total;
+
+        self.colors.extend(other.colors);
+        self.weights.extend(
+            other
+                .weights
+                .iter()
+                .map(|pixel_weight| (f64::from(*pixel_weight) * scale).round().max(1.0) as u32),
+        );
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Same quantizer `ImageReader::extract_color` uses, except that the
+    /// cluster populations are recounted using the pixel weights.
+    pub fn quantize(&self) -> QuantizerResult {
+        let mut result = QuantizerCelebi::quantize(&self.colors, 128);
+
+        for count in result.color_to_count.values_mut() {
+            *count = 0;
+        }
+        for (color, weight) in self.colors.iter().zip(&self.weights) {
+            if let Some(cluster) = result.input_pixel_to_cluster_pixel.get(color)
+                && let Some(count) = result.color_to_count.get_mut(cluster)
+            {
+                *count += weight;
+            }
+        }
+        result.color_to_count.retain(|_, count| *count > 0);
+
+        result
+    }
+}
+
+/// Decodes the image at `path` and returns its pixels, ready for
+/// `Pixels::quantize`. Animations and videos contribute the pixels of
+/// several frames, or only of `Options::frame` if it is set.
+pub fn load_pixels(path: &Path, options: &Options) -> Result<Pixels, String> {
+    let samples = match extension(path) {
+        Some("svg" | "svgz") => vec![to_sample(rasterize_svg(path, options)?)],
+        Some("gif" | "png" | "apng" | "webp") => decode_animation(path, options)?,
+        Some("mp4" | "mkv" | "webm" | "mov") => video_frames(path, options)?,
+        _ => vec![to_sample(decode(path)?)],
+    };
+
+    let mut pixels = Pixels::new();
+    for sample in &samples {
+        pixels.add_image(sample);
+    }
+
+    if pixels.is_empty() {
+        Err("the image is fully transparent".to_owned())
+    } else {
+        Ok(pixels)
+    }
+}
+
+/// Whether `load_pixels` knows how to read the file, going by its extension.
+pub fn is_supported(path: &Path) -> bool {
+    matches!(
+        extension(path),
+        Some(
+            "jpg"
+                | "jpeg"
+                | "png"
+                | "apng"
+                | "webp"
+                | "gif"
+                | "tif"
+                | "tiff"
+                | "hdr"
+                | "exr"
+                | "svg"
+                | "svgz"
+                | "mp4"
+                | "mkv"
+                | "webm"
+                | "mov"
+        )
+    )
+}
+
+fn extension(path: &Path) -> Option<&str> {
+    path.extension().and_then(|ext| ext.to_str())
+}
+
+fn decode(path: &Path) -> Result<DynamicImage, String> {
+    ImageReader::open(path)
+        .map_err(|err| format!("failed to read image: {err}"))?
+        .with_guessed_format()
+        .map_err(|err| format!("failed to read image: {err}"))?
+        .decode()
+        .map_err(|err| format!("failed to decode image: {err}"))
+}
+
+/// Samples the frames of a GIF, APNG or animated WebP. Files that turn out
+/// not to be animated are decoded like any other image.
+fn decode_animation(path: &Path, options: &Options) -> Result<Vec<RgbaImage>, String> {
+    let open = || {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|err| format!("failed to read image: {err}"))
+    };
+    let decode_err = |err: image::ImageError| format!("failed to decode image: {err}");
+
+    let frames = match extension(path) {
+        Some("gif") => GifDecoder::new(open()?).map_err(decode_err)?.into_frames(),
+        Some("webp") => {
+            let decoder = WebPDecoder::new(open()?).map_err(decode_err)?;
+            if !decoder.has_animation() {
+                return Ok(vec![to_sample(decode(path)?)]);
+            }
+            decoder.into_frames()
+        }
+        _ => {
+            let decoder = PngDecoder::new(open()?).map_err(decode_err)?;
+            if !decoder.is_apng().map_err(decode_err)? {
+                return Ok(vec![to_sample(decode(path)?)]);
+            }
+            decoder.apng().map_err(decode_err)?.into_frames()
+        }
+    };
+
+    sample_frames(frames, options.frame)
+}
+
+fn sample_frames(frames: Frames, frame: Option<usize>) -> Result<Vec<RgbaImage>, String> {
+    let mut samples = Vec::new();
+    // only every `stride`th frame is kept, so long animations never have
+    // more than twice `SAMPLE_FRAMES` samples at once
+    let mut stride = 1;
+
+    for (index, decoded) in frames.enumerate() {
+        if frame.is_some_and(|frame| frame != index) {
+            continue;
+        }
+
+        let decoded = decoded.map_err(|err| format!("failed to decode frame {index}: {err}"))?;
+        if index % stride != 0 {
+            continue;
+        }
+        samples.push(to_sample(DynamicImage::ImageRgba8(decoded.into_buffer())));
+
+        if frame.is_some() {
+            return Ok(samples);
+        }
+
+        if samples.len() == 2 * SAMPLE_FRAMES {
+            stride *= 2;
+            let mut kept = false;
+            samples.retain(|_| {
+                kept = !kept;
+                kept
+            });
+        }
+    }
+
+    if let Some(frame) = frame {
+        return Err(format!("the animation has no frame {frame}"));
+    }
+
+    // spread the picked frames evenly over the whole animation
+    let count = samples.len();
+    if count > SAMPLE_FRAMES {
+        samples = (0..SAMPLE_FRAMES)
+            .map(|i| samples[i * count / SAMPLE_FRAMES].clone())
+            .collect();
+    }
+
+    Ok(samples)
+}
+
+/// Grabs frames of a video with `ffmpeg`, evenly spaced over its duration
+/// (as reported by `ffprobe`).
+fn video_frames(path: &Path, options: &Options) -> Result<Vec<RgbaImage>, String> {
+    if let Some(frame) = options.frame {
+        let select = format!("select=eq(n\\,{frame})");
+        let image = ffmpeg_frame(path, None, Some(&select))
+            .map_err(|err| format!("couldn't get frame {frame} of the video: {err}"))?;
+
+        return Ok(vec![to_sample(image)]);
+    }
+
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .map_err(|err| format!("couldn't run `ffprobe` to read the video: {err}"))?;
+    let duration: f64 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|_| "couldn't read the duration of the video".to_owned())?;
+
+    let mut samples = Vec::new();
+    for i in 0..SAMPLE_FRAMES {
+        let time = format!("{:.3}", duration * (i as f64 + 0.5) / SAMPLE_FRAMES as f64);
+        let image = ffmpeg_frame(path, Some(&time), None)?;
+
+        samples.push(to_sample(image));
+    }
+
+    Ok(samples)
+}
+
+// the first frame after `seek` that passes `filter` gets written to stdout
+// as a PNG
+fn ffmpeg_frame(
+    path: &Path,
+    seek: Option<&str>,
+    filter: Option<&str>,
+) -> Result<DynamicImage, String> {
+    let mut command = Command::new("ffmpeg");
+    command.args(["-v", "error"]);
+    if let Some(time) = seek {
+        command.args(["-ss", time]);
+    }
+    command.arg("-i").arg(path);
+    if let Some(filter) = filter {
+        command.args(["-vf", filter]);
+    }
+
+    let output = command
+        .args(["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png", "-"])
+        .output()
+        .map_err(|err| format!("couldn't run `ffmpeg` to read the video: {err}"))?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(format!(
+            "`ffmpeg` couldn't read a frame of {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    image::load_from_memory(&output.stdout).map_err(|err| format!("failed to decode frame: {err}"))
+}
+
+/// Renders an SVG with `rsvg-convert`, there is no SVG renderer among our
+/// dependencies.
+fn rasterize_svg(path: &Path, options: &Options) -> Result<DynamicImage, String> {
+    let output = Command::new("rsvg-convert")
+        .arg("--width")
+        .arg(options.svg_size.to_string())
+        .arg("--height")
+        .arg(options.svg_size.to_string())
+        .arg("--keep-aspect-ratio")
+        .arg("--format")
+        .arg("png")
+        .arg(path)
+        .output()
+        .map_err(|err| format!("couldn't run `rsvg-convert` to rasterize the SVG: {err}"))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "`rsvg-convert` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let mut image = image::load_from_memory(&output.stdout)
+        .map_err(|err| format!("failed to decode rasterized SVG: {err}"))?
+        .into_rgba8();
+
+    if let Some(background) = options.svg_background {
+        fill_background(&mut image, background);
+    }
+
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+fn fill_background(image: &mut RgbaImage, background: Argb) {
+    let blend = |color: u8, background: u8, alpha: u32| {
+        ((u32::from(color) * alpha + u32::from(background) * (255 - alpha) + 127) / 255) as u8
+    };
+
+    for pixel in image.pixels_mut() {
+        let [red, green, blue, alpha] = pixel.0;
+        let alpha = u32::from(alpha);
+
+        *pixel = Rgba([
+            blend(red, background.red, alpha),
+            blend(green, background.green, alpha),
+            blend(blue, background.blue, alpha),
+            255,
+        ]);
+    }
+}
+
+/// Scales `image` down to `SAMPLE_SIZE` and converts it to 8-bit sRGB.
+///
+/// 16-bit images are scaled while still in full precision, HDR images
+/// (which decode to linear floats) are tone-mapped first.
+///
+/// `imageops::resize` expects premultiplied alpha, which decoded images
+/// don't have, so the colors get premultiplied for scaling. Otherwise the
+/// color of transparent pixels (usually black) bleeds into the edges.
+fn to_sample(image: DynamicImage) -> RgbaImage {
+    match image {
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+            let small = resize_float(image.into_rgba32f());
+            float_to_rgba8(&small, |rgb| tone_map(rgb).map(linear_to_srgb))
+        }
+        DynamicImage::ImageLuma16(_)
+        | DynamicImage::ImageLumaA16(_)
+        | DynamicImage::ImageRgb16(_)
+        | DynamicImage::ImageRgba16(_) => {
+            let small = resize_float(image.into_rgba32f());
+            float_to_rgba8(&small, |rgb| rgb)
+        }
+        _ => resize(image.into_rgba8()),
+    }
+}
+
+fn resize(mut image: RgbaImage) -> RgbaImage {
+    for pixel in image.pixels_mut() {
+        let alpha = u16::from(pixel[3]);
+        for channel in &mut pixel.0[..3] {
+            *channel = ((u16::from(*channel) * alpha + 127) / 255) as u8;
+        }
+    }
+
+    let mut small = imageops::resize(&image, SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Triangle);
+
+    for pixel in small.pixels_mut() {
+        let alpha = u16::from(pixel[3]);
+        if alpha == 0 {
+            continue;
+        }
+        for channel in &mut pixel.0[..3] {
+            *channel = ((u16::from(*channel) * 255 + alpha / 2) / alpha).min(255) as u8;
+        }
+    }
+
+    small
+}
+
+fn resize_float(mut image: Rgba32FImage) -> Rgba32FImage {
+    for pixel in image.pixels_mut() {
+        let alpha = pixel[3];
+        for channel in &mut pixel.0[..3] {
+            *channel *= alpha;
+        }
+    }
+
+    let mut small = imageops::resize(&image, SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Triangle);
+
+    for pixel in small.pixels_mut() {
+        let alpha = pixel[3];
+        if alpha <= 0.0 {
+            continue;
+        }
+        for channel in &mut pixel.0[..3] {
+            *channel /= alpha;
+        }
+    }
+
+    small
+}
+
+// `encode` maps the color channels into the 0.0..=1.0 sRGB range, alpha is
+// always linear
+fn float_to_rgba8(image: &Rgba32FImage, encode: impl Fn([f32; 3]) -> [f32; 3]) -> RgbaImage {
+    let to_u8 = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let [red, green, blue, alpha] = image.get_pixel(x, y).0;
+        let [red, green, blue] = encode([red, green, blue]);
+
+        Rgba([to_u8(red), to_u8(green), to_u8(blue), to_u8(alpha)])
+    })
+}
+
+// Reinhard on the luminance, so bright areas get compressed without
+// shifting their hue
+fn tone_map(rgb: [f32; 3]) -> [f32; 3] {
+    let [red, green, blue] = rgb.map(|value| value.max(0.0));
+    let luminance = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+
+    if luminance <= 0.0 {
+        return [0.0; 3];
+    }
+
+    let scale = 1.0 / (1.0 + luminance);
+    [red * scale, green * scale, blue * scale]
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
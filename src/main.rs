@@ -1,109 +1,559 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use std::{
     env::{self},
-    fs::File,
-    io::Write,
-    path::PathBuf,
-    process::Command,
+    path::{Path, PathBuf},
+    process::{self, Command},
+    str::FromStr,
 };
 
 use material_colors::{
-    color::Argb,
-    dynamic_color::{Variant, variant},
-    image::{FilterType, ImageReader},
-    theme::{Theme, ThemeBuilder},
+    color::Argb, dynamic_color::Variant, quantize::QuantizerResult, theme::Theme,
 };
-use serde_json;
+use serde::{Deserialize, Serialize};
+
+mod adjust;
+mod apply;
+mod batch;
+mod cache;
+mod config;
+mod css;
+mod custom;
+mod hash;
+mod hex;
+mod history;
+mod layers;
+mod loader;
+mod metadata;
+mod mode;
+mod overrides;
+mod rotate;
+mod saved;
+mod schedule;
+mod seed;
+mod share;
+mod state;
+mod wallpapers;
 
 #[derive(Parser, Debug)]
 #[command(name = "Material Design color generator")]
 #[command(about = "A program for generating Googles Material Design 3 colorshemes.")]
 #[command(version = "0.0.1", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Arguments {
-    // path to image
-    #[arg(short, long, value_name = "IMAGE")]
-    image: PathBuf,
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    // without a subcommand the arguments of `generate` are used
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Generate a theme from one or more images (the default)
+    Generate(GenerateArgs),
+    /// Cycle through the images of a directory, regenerating the theme on
+    /// every step. Send SIGUSR1 (`pkill -USR1 -f "mat3 rotate"`) to skip to
+    /// the next image early
+    Rotate(rotate::RotateArgs),
+    /// Quantize every image of a directory ahead of time and show the
+    /// source color each one gives
+    Batch(batch::BatchArgs),
+    /// Clear the cache of quantized images or show its size
+    Cache(cache::CacheArgs),
+    /// Write the outputs of a saved theme again
+    Apply(apply::ApplyArgs),
+    /// Show the themes written so far, newest first
+    History,
+    /// Go back to the theme written before the last one
+    Undo(OutputArgs),
+    /// Keep the last generated theme under a name
+    Save(saved::NameArgs),
+    /// List the saved themes
+    List,
+    /// Write the outputs of a saved theme
+    Load(saved::LoadArgs),
+    /// Delete a saved theme
+    Delete(saved::NameArgs),
+    /// Print a short code of the last theme (or a saved one) that
+    /// `mat3 apply --code` turns back into the same theme anywhere
+    Share(share::ShareArgs),
+    /// Switch the outputs of the last theme to light or dark, without
+    /// generating it again
+    Mode(mode::ModeArgs),
+}
+
+#[derive(Args, Debug)]
+struct GenerateArgs {
+    // path to image, can be given multiple times to pool the pixels of
+    // several images into one theme. Directories get replaced by one of the
+    // images inside them
+    #[arg(short, long, value_name = "IMAGE[:WEIGHT]", num_args = 1..)]
+    #[arg(required_unless_present = "seed_text", value_parser = parse_image_arg)]
+    image: Vec<(PathBuf, f64)>,
+
+    /// Build the theme from a color hashed out of this text instead of an
+    /// image, the same text always gives the same theme
+    #[arg(long, value_name = "TEXT", conflicts_with = "image")]
+    seed_text: Option<String>,
+
+    /// Which image to use when a directory is given
+    #[arg(long, value_enum, default_value_t = wallpapers::Pick::Random)]
+    pick: wallpapers::Pick,
+
+    #[command(flatten)]
+    theme: ThemeArgs,
+}
 
+// options shared by everything that generates a theme
+#[derive(Args, Debug)]
+struct ThemeArgs {
     #[arg(short, long)]
     variant: Option<String>,
 
-    #[arg(short, long)]
-    scheme: Option<String>,
+    /// Which scheme to write [default: dark]
+    #[arg(short, long, value_enum)]
+    scheme: Option<mode::SchemeMode>,
+
+    /// Average image tone (0-100) from which `--scheme auto` picks light
+    /// [default: 50]
+    #[arg(long, value_name = "TONE", value_parser = adjust::parse_tone)]
+    auto_threshold: Option<f64>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+
+    /// Width and height SVG images get rasterized at
+    #[arg(long, value_name = "PIXELS", default_value_t = 512)]
+    svg_size: u32,
+
+    /// Color drawn behind transparent areas of SVG images
+    #[arg(long, value_name = "COLOR", value_parser = parse_color_arg)]
+    svg_background: Option<Argb>,
+
+    /// Only use this frame of an animated image or video (starting at 0)
+    /// instead of merging several
+    #[arg(long, value_name = "N")]
+    frame: Option<usize>,
+
+    /// Config file to use instead of ~/.config/mat3/config.toml
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// What to do when the image is (nearly) grayscale [default: warn]
+    #[arg(long, value_enum)]
+    low_chroma: Option<seed::LowChroma>,
+
+    /// Average chroma below which an image counts as grayscale [default: 8]
+    #[arg(long, value_name = "CHROMA", value_parser = seed::parse_chroma)]
+    low_chroma_threshold: Option<f64>,
 
+    /// Source color for `--low-chroma fallback` [default: #4285f4]
+    #[arg(long, value_name = "COLOR", value_parser = parse_color_arg)]
+    fallback_seed: Option<Argb>,
+
+    /// Key color of the primary palette, instead of the image's
+    #[arg(long, value_name = "COLOR", value_parser = parse_color_arg)]
+    primary: Option<Argb>,
+
+    /// Key color of the secondary palette, instead of the image's
+    #[arg(long, value_name = "COLOR", value_parser = parse_color_arg)]
+    secondary: Option<Argb>,
+
+    /// Key color of the tertiary palette, instead of the image's
+    #[arg(long, value_name = "COLOR", value_parser = parse_color_arg)]
+    tertiary: Option<Argb>,
+
+    /// Key color of the neutral palette, instead of the image's
+    #[arg(long, value_name = "COLOR", value_parser = parse_color_arg)]
+    neutral: Option<Argb>,
+
+    /// Key color of the neutral variant palette, instead of the image's
+    #[arg(long, value_name = "COLOR", value_parser = parse_color_arg)]
+    neutral_variant: Option<Argb>,
+
+    /// Key color of the error palette, instead of the default red
+    #[arg(long, value_name = "COLOR", value_parser = parse_color_arg)]
+    error: Option<Argb>,
+
+    /// Extra named color, harmonized toward the source color unless
+    /// `noblend` is given. Can be used multiple times
+    #[arg(long = "custom", value_name = "NAME=COLOR[,blend|,noblend]")]
+    #[arg(value_parser = custom::parse_custom_arg)]
+    custom_colors: Vec<custom::Custom>,
+
+    /// Add this many colors of the image as `accent1`..`accentN` custom
+    /// colors, harmonized toward the source color [default: 0]
+    #[arg(long, value_name = "N")]
+    accents: Option<usize>,
+
+    /// Mix the source color toward this color, or toward the source color
+    /// of this image
+    #[arg(long, value_name = "COLOR|IMAGE")]
+    blend: Option<String>,
+
+    /// How far `--blend` mixes, 0 keeps the source color and 1 replaces it
+    #[arg(long, value_name = "RATIO", default_value_t = 0.5)]
+    #[arg(value_parser = adjust::parse_ratio, requires = "blend")]
+    ratio: f64,
+
+    /// Degrees to rotate the hue of the source color by
+    #[arg(
+        long,
+        value_name = "DEGREES",
+        default_value_t = 0.0,
+        allow_negative_numbers = true
+    )]
+    #[arg(value_parser = adjust::parse_hue_shift)]
+    hue_shift: f64,
+
+    /// Factor to multiply the chroma of the source color with, below 1 is
+    /// less saturated
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    #[arg(value_parser = adjust::parse_chroma_scale)]
+    chroma_scale: f64,
+
+    /// Tone (0-100) to give the source color
+    #[arg(long, value_name = "TONE", value_parser = adjust::parse_tone)]
+    tone: Option<f64>,
+
+    /// Always decode the images, without looking into or filling the cache
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Contrast from -1 (reduced) to 1 (high) [default: 0]
+    #[arg(long, value_name = "LEVEL", allow_negative_numbers = true)]
+    #[arg(value_parser = parse_contrast)]
+    contrast: Option<f64>,
+}
+
+// where the colors get written to
+#[derive(Args, Debug)]
+struct OutputArgs {
     #[arg(short, long, value_name = "CONFIG_DIR")]
     waybar_conf_dir: Option<String>,
+
+    /// Write both schemes instead of only the chosen one, as
+    /// `colors-light.css` and `colors-dark.css` for Waybar and as
+    /// `[data-theme]` selectors in the CSS
+    #[arg(long, overrides_with = "no_both_schemes")]
+    both_schemes: bool,
+
+    /// Only write the chosen scheme, even if both were written last time
+    #[arg(long)]
+    no_both_schemes: bool,
+
+    /// Also write the tones of every palette, like `primary_40`, either the
+    /// standard tones or the ones given
+    #[arg(long, value_name = "TONES", num_args = 0.., value_delimiter = ',')]
+    #[arg(value_parser = clap::value_parser!(u8).range(0..=100))]
+    #[arg(overrides_with = "no_palette_tones")]
+    palette_tones: Option<Vec<u8>>,
+
+    /// Stop writing the palette tones
+    #[arg(long)]
+    no_palette_tones: bool,
 }
 
 fn main() {
     let args = Arguments::parse();
 
-    check_args(args);
-
-    sigusr2_waybar();
+    match args.command {
+        Some(Commands::Generate(arguments)) => generate(arguments),
+        Some(Commands::Rotate(arguments)) => rotate::rotate(arguments),
+        Some(Commands::Apply(arguments)) => apply::apply(arguments),
+        Some(Commands::Batch(arguments)) => batch::batch(arguments),
+        Some(Commands::Cache(arguments)) => cache::cache(arguments),
+        Some(Commands::Mode(arguments)) => mode::switch(arguments),
+        Some(Commands::History) => history::show(),
+        Some(Commands::Undo(arguments)) => history::undo(arguments),
+        Some(Commands::Save(arguments)) => saved::save(arguments),
+        Some(Commands::List) => saved::list(),
+        Some(Commands::Load(arguments)) => saved::load(arguments),
+        Some(Commands::Delete(arguments)) => saved::delete(arguments),
+        Some(Commands::Share(arguments)) => share::share(arguments),
+        None => generate(args.generate),
+    }
 }
 fn sigusr2_waybar() {
     Command::new("pkill")
         .arg("-USR2")
         .arg("waybar")
-        .spawn()
+        .status()
         .expect("error while restarting Waybar");
 }
-fn check_args(arguments: Arguments) {
-    let username = env::var("USER").expect("couldn't read environment variable `$USER`.");
+// written to `theme.json`, output flags that aren't given keep the last value
+#[derive(Serialize, Deserialize)]
+struct Outputs {
+    config_dir: String,
+    both_schemes: bool,
+    // empty when no palette tones are written
+    palette_tones: Vec<u8>,
+}
 
-    let mut is_dark = true;
-    let mut variant = Variant::TonalSpot;
-    let mut config_dir = "/home/".to_owned() + &username + "/.config";
+fn check_outputs(arguments: OutputArgs) -> Outputs {
+    let last = state::read_json::<metadata::LastOutputs>(metadata::THEME_FILE).outputs;
 
-    config_dir = match arguments.waybar_conf_dir {
-        Some(path) => path,
-        None => config_dir,
+    let config_dir = match (arguments.waybar_conf_dir, &last) {
+        (Some(path), _) => path,
+        (None, Some(last)) => last.config_dir.clone(),
+        (None, None) => {
+            let username = env::var("USER").expect("couldn't read environment variable `$USER`.");
+            "/home/".to_owned() + &username + "/.config"
+        }
     };
 
+    let both_schemes = if arguments.both_schemes || arguments.no_both_schemes {
+        arguments.both_schemes
+    } else {
+        last.as_ref().is_some_and(|last| last.both_schemes)
+    };
+
+    let mut palette_tones = match arguments.palette_tones {
+        Some(tones) if tones.is_empty() => css::STANDARD_TONES.to_vec(),
+        Some(tones) => tones,
+        None if arguments.no_palette_tones => Vec::new(),
+        None => last.map(|last| last.palette_tones).unwrap_or_default(),
+    };
+    palette_tones.sort();
+    palette_tones.dedup();
+
+    Outputs {
+        config_dir,
+        both_schemes,
+        palette_tones,
+    }
+}
+
+// everything `run` needs besides the images
+struct Settings {
+    outputs: Outputs,
+    mode_options: mode::Options,
+    variant: Variant,
+    load_options: loader::Options,
+    cache: Option<cache::Cache>,
+    seed_options: seed::Options,
+    key_colors: overrides::KeyColors,
+    custom_colors: Vec<custom::Custom>,
+    accents: usize,
+    // color the source gets mixed toward, and how far
+    blend: Option<(Argb, f64)>,
+    adjustments: adjust::Adjustments,
+    contrast: f64,
+}
+
+fn check_args(arguments: ThemeArgs) -> Settings {
+    let config_path = arguments.config.unwrap_or_else(config::default_path);
+    let config = match config::load(&config_path) {
+        Ok(config) => config,
+        Err(err) => {
+            println!("{err}");
+            process::exit(1);
+        }
+    };
+
+    let outputs = check_outputs(arguments.output);
+    let mut variant = Variant::TonalSpot;
+
     variant = match arguments.variant {
         Some(var) => parse_variant(var),
         None => variant,
     };
 
-    is_dark = match arguments.scheme {
-        Some(scheme) => {
-            if scheme == "light" {
-                false
-            } else {
-                true
+    let scheme = arguments
+        .scheme
+        .or(config.scheme)
+        .unwrap_or(mode::SchemeMode::Dark);
+    let schedule = if scheme == mode::SchemeMode::Schedule {
+        match schedule::Schedule::from_config(config.schedule) {
+            Ok(schedule) => Some(schedule),
+            Err(err) => {
+                println!("{err}");
+                process::exit(1);
             }
         }
-        None => is_dark,
+    } else {
+        None
+    };
+    let mode_options = mode::Options {
+        scheme,
+        auto_threshold: arguments
+            .auto_threshold
+            .or(config.auto_threshold)
+            .unwrap_or(mode::DEFAULT_AUTO_THRESHOLD),
+        schedule,
+    };
+
+    let load_options = loader::Options {
+        svg_size: arguments.svg_size,
+        svg_background: arguments.svg_background,
+        frame: arguments.frame,
+    };
+
+    let seed_options = seed::Options {
+        low_chroma: arguments
+            .low_chroma
+            .or(config.low_chroma)
+            .unwrap_or(seed::LowChroma::Warn),
+        low_chroma_threshold: arguments
+            .low_chroma_threshold
+            .or(config.low_chroma_threshold)
+            .unwrap_or(seed::DEFAULT_LOW_CHROMA_THRESHOLD),
+        fallback_seed: arguments
+            .fallback_seed
+            .or(config.fallback_seed)
+            .unwrap_or(seed::DEFAULT_FALLBACK_SEED),
+        exclude_hues: config.exclude_hues,
+        prefer_hues: config.prefer_hues,
+        min_chroma: config.min_chroma.unwrap_or(0.0),
+    };
+
+    let key_colors = overrides::KeyColors {
+        primary: arguments.primary,
+        secondary: arguments.secondary,
+        tertiary: arguments.tertiary,
+        neutral: arguments.neutral,
+        neutral_variant: arguments.neutral_variant,
+        error: arguments.error,
+    }
+    .or(config.key_colors);
+
+    let mut custom_colors = match custom::from_config(config.custom_colors) {
+        Ok(custom_colors) => custom_colors,
+        Err(err) => {
+            println!("{}: {err}", config_path.display());
+            process::exit(1);
+        }
     };
+    // flags replace config entries with the same name
+    for color in arguments.custom_colors {
+        custom_colors.retain(|existing| existing.name != color.name);
+        custom_colors.push(color);
+    }
+
+    let accents = arguments.accents.or(config.accents).unwrap_or(0);
+    if let Err(err) = custom::check_clashes(&custom_colors, accents) {
+        println!("{err}");
+        process::exit(1);
+    }
 
-    if is_image(&arguments.image).unwrap() {
-        run(
-            arguments.image.display().to_string(),
-            config_dir,
-            is_dark,
-            variant,
+    let blend =
+        arguments.blend.map(
+            |arg| match adjust::blend_target(&arg, &load_options, &seed_options) {
+                Ok(target) => (target, arguments.ratio),
+                Err(err) => {
+                    println!("{err}");
+                    process::exit(1);
+                }
+            },
         );
+
+    let cache = if arguments.no_cache {
+        None
     } else {
-        println!("not an image");
+        Some(cache::Cache::new(
+            config.cache_size.unwrap_or(cache::DEFAULT_MAX_ENTRIES),
+        ))
+    };
+
+    Settings {
+        outputs,
+        mode_options,
+        variant,
+        load_options,
+        cache,
+        seed_options,
+        key_colors,
+        custom_colors,
+        accents,
+        blend,
+        adjustments: adjust::Adjustments {
+            hue_shift: arguments.hue_shift,
+            chroma_scale: arguments.chroma_scale,
+            tone: arguments.tone,
+        },
+        contrast: arguments
+            .contrast
+            .or(config.contrast)
+            .unwrap_or(0.0)
+            .clamp(-1.0, 1.0),
+    }
+}
+
+fn generate(mut arguments: GenerateArgs) {
+    let settings = check_args(arguments.theme);
+
+    if let Some(text) = arguments.seed_text {
+        let (theme, metadata) = build_theme(
+            seed::from_text(&text),
+            settings.variant.clone(),
+            &[],
+            Vec::new(),
+            &settings,
+        );
+        let is_dark = mode::is_dark(&settings.mode_options, None);
+        write_theme(&theme, &metadata, is_dark, &settings.outputs);
+        sigusr2_waybar();
+        return;
+    }
+
+    for (path, _) in &mut arguments.image {
+        if path.is_dir() {
+            match wallpapers::pick(path, arguments.pick) {
+                Ok(picked) => *path = picked,
+                Err(err) => {
+                    println!("{err}");
+                    process::exit(1);
+                }
+            }
+            println!("{}", path.display());
+        }
+
+        match is_image(path) {
+            Ok(true) => {}
+            Ok(false) => {
+                println!("{} is not an image", path.display());
+                process::exit(1);
+            }
+            Err(err) => {
+                println!("{}: {err}", path.display());
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Err(err) = run(&arguments.image, &settings) {
+        println!("{err}");
+        process::exit(1);
+    }
+
+    sigusr2_waybar();
+}
+
+// `path` or `path:weight`, the weight defaults to 1
+fn parse_image_arg(arg: &str) -> Result<(PathBuf, f64), String> {
+    if let Some((path, weight)) = arg.rsplit_once(':')
+        && let Ok(weight) = weight.parse::<f64>()
+    {
+        if weight <= 0.0 || !weight.is_finite() {
+            return Err(format!(
+                "the weight of {path} has to be a number greater than 0"
+            ));
+        }
+        return Ok((PathBuf::from(path), weight));
     }
+
+    Ok((PathBuf::from(arg), 1.0))
 }
 
-fn is_image(path: &PathBuf) -> Result<bool, &str> {
+fn is_image(path: &Path) -> Result<bool, &str> {
     if path.is_dir() {
         Err("the given path is a directory")
     } else if !path.exists() {
-        Err("the given path is a does not exist")
+        Err("the given path does not exist")
     } else {
         match path.extension() {
-            Some(ext) => {
-                if ext == "jpg" || ext == "jpeg" {
-                    Ok(true)
-                } else if ext == "png" || ext == "webp" {
-                    Ok(true)
-                } else {
-                    Ok(false)
-                }
-            }
+            Some(_) => Ok(loader::is_supported(path)),
             None => {
                 Err("couldn't parse file extension, is either empty or something else happened")
             }
@@ -132,38 +582,162 @@ fn parse_variant(variant_string: String) -> Variant {
     }
 }
 
-fn run(img_path: String, config_dir: String, is_dark: bool, variant: Variant) {
-    let mut img_data = ImageReader::open(img_path).expect("failed to read image");
+fn parse_contrast(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(contrast) if (-1.0..=1.0).contains(&contrast) => Ok(contrast),
+        _ => Err(format!("`{arg}` is not a contrast level between -1 and 1")),
+    }
+}
 
-    img_data.resize(128, 128, FilterType::Triangle);
+fn parse_color_arg(arg: &str) -> Result<Argb, String> {
+    Argb::from_str(arg).map_err(|_| format!("`{arg}` is not a hex color like #4285f4"))
+}
 
-    let theme = ThemeBuilder::with_source(ImageReader::extract_color(&img_data))
-        .variant(variant)
-        .build();
+fn build_theme(
+    seed: Argb,
+    variant: Variant,
+    accents: &[Argb],
+    images: Vec<metadata::SourceImage>,
+    settings: &Settings,
+) -> (Theme, metadata::Metadata) {
+    let source = match settings.blend {
+        Some((target, ratio)) => adjust::blend(seed, target, ratio),
+        None => seed,
+    };
+    let source = settings.adjustments.apply(source);
 
-    let theme_json = serde_json::to_string_pretty(&theme).unwrap();
+    let accents = accents.iter().enumerate().map(|(i, color)| custom::Custom {
+        name: format!("accent{}", i + 1),
+        value: *color,
+        blend: true,
+    });
 
-    let mut json_file = File::create("theme.json").unwrap();
-    json_file.write_all(theme_json.as_bytes()).unwrap();
+    let recipe = metadata::Recipe {
+        source,
+        variant,
+        contrast: settings.contrast,
+        key_colors: settings.key_colors.clone(),
+        custom_colors: accents
+            .chain(settings.custom_colors.iter().cloned())
+            .collect(),
+    };
 
-    create_waybar_css(&theme, is_dark, &(config_dir + "/waybar/colors.css"));
-    create_css(
-        &theme,
+    let metadata = metadata::Metadata {
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        images,
+        seed,
+        adjustments: settings.adjustments,
+        recipe,
+    };
+    (metadata.recipe.build(), metadata)
+}
+
+/// Generates the theme for `images` and writes all outputs. The theme is
+/// returned so it can be written again later, like `rotate` does when the
+/// scheme schedule switches between light and dark.
+fn run(
+    images: &[(PathBuf, f64)],
+    settings: &Settings,
+) -> Result<(Theme, metadata::Metadata), String> {
+    let (quantized, sources) = quantize_images(images, settings)?;
+    let choice = seed::choose(&quantized, &settings.variant, &settings.seed_options);
+    for warning in &choice.warnings {
+        println!("warning: {warning}");
+    }
+    let accents = seed::accents(
+        &quantized,
+        choice.seed,
+        settings.accents,
+        &settings.seed_options,
+    );
+
+    let (theme, metadata) = build_theme(choice.seed, choice.variant, &accents, sources, settings);
+    let is_dark = mode::is_dark(&settings.mode_options, Some(&quantized));
+    write_theme(&theme, &metadata, is_dark, &settings.outputs);
+
+    Ok((theme, metadata))
+}
+
+/// The colors of `images` merged and quantized, from the cache if they
+/// were quantized before, along with what the metadata records of them.
+fn quantize_images(
+    images: &[(PathBuf, f64)],
+    settings: &Settings,
+) -> Result<(QuantizerResult, Vec<metadata::SourceImage>), String> {
+    let mut sources = Vec::new();
+    for (path, weight) in images {
+        sources.push(metadata::SourceImage {
+            path: path.canonicalize().unwrap_or(path.clone()),
+            hash: hash::file_hash(path)?,
+            weight: *weight,
+        });
+    }
+
+    let key = cache::key(&sources, &settings.load_options);
+    if let Some(cache) = &settings.cache
+        && let Some(quantized) = cache.get(&key)
+    {
+        return Ok((quantized, sources));
+    }
+
+    // only the ratios of the weights matter, scaled to add up to 1 the
+    // pooled counts can't overflow however large the weights are
+    let max_weight = images.iter().map(|(_, weight)| *weight).fold(0.0, f64::max);
+    let total_weight: f64 = images.iter().map(|(_, weight)| weight / max_weight).sum();
+
+    let mut pixels = loader::Pixels::new();
+    for (path, weight) in images {
+        let image_pixels = loader::load_pixels(path, &settings.load_options)
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        pixels.merge(image_pixels, weight / max_weight / total_weight);
+    }
+
+    let quantized = pixels.quantize();
+    if let Some(cache) = &settings.cache {
+        cache.insert(&key, &quantized);
+    }
+
+    Ok((quantized, sources))
+}
+
+fn write_theme(theme: &Theme, metadata: &metadata::Metadata, is_dark: bool, outputs: &Outputs) {
+    let theme_file = metadata::ThemeFile {
+        theme,
+        metadata,
+        is_dark,
+        outputs,
+    };
+    // `mat3 mode` and `mat3 apply` work with this one
+    state::write_json(metadata::THEME_FILE, &theme_file);
+    history::record(metadata, is_dark);
+
+    css::create_waybar_css(
+        theme,
         is_dark,
-        &"/home/oshiro/.config/vesktop/themes/Actual Material design colors/colors.css".to_owned(),
+        outputs.both_schemes,
+        &outputs.palette_tones,
+        &(outputs.config_dir.clone() + "/waybar/colors.css"),
+    );
+    css::create_css(
+        theme,
+        is_dark,
+        outputs.both_schemes,
+        &outputs.palette_tones,
+        "/home/oshiro/.config/vesktop/themes/Actual Material design colors/colors.css",
     );
 
     println!("{:?}", theme.source);
 }
-fn create_css(theme: &Theme, is_dark: bool, config_file_path: &String) {
-    let css_string = theme_to_css(&theme, is_dark).unwrap();
-    let mut css_file = File::create(config_file_path.to_owned()).unwrap();
-    css_file.write_all(css_string.as_bytes()).unwrap();
-}
-fn create_waybar_css(theme: &Theme, is_dark: bool, config_file_path: &String) {
-    let css_string = theme_to_waybar_css(&theme, is_dark).unwrap();
-    let mut css_file = File::create(config_file_path.to_owned()).unwrap();
-    css_file.write_all(css_string.as_bytes()).unwrap();
+/// The directory in `var`, like `$XDG_CACHE_HOME`, or `fallback` in the home
+/// directory when it isn't set.
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    match env::var(var) {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = env::var("HOME").expect("couldn't read environment variable `$HOME`.");
+            PathBuf::from(home).join(fallback)
+        }
+    }
 }
 
 fn print_color(color: Argb) {
@@ -173,508 +747,42 @@ fn print_color(color: Argb) {
     )
 }
 
-fn theme_to_css(theme: &Theme, is_dark: bool) -> Result<String, ()> {
-    let mut css_buf = String::new();
-
-    css_buf += &("# Source: ".to_owned() + &theme.source.to_hex() + "\n");
-    css_buf += ":root {\n";
-    if is_dark {
-        css_buf += &("  --".to_owned() + "primary: #" + &theme.schemes.dark.primary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_primary: #" + &theme.schemes.dark.on_primary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "primary_container: #" + &theme.schemes.dark.primary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_primary_container: #" + &theme.schemes.dark.on_primary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "inverse_primary: #" + &theme.schemes.dark.inverse_primary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "primary_fixed: #" + &theme.schemes.dark.primary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "primary_fixed_dim: #" + &theme.schemes.dark.primary_fixed_dim.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_primary_fixed: #" + &theme.schemes.dark.on_primary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_primary_fixed_variant: #" + &theme.schemes.dark.on_primary_fixed_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "secondary: #" + &theme.schemes.dark.secondary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_secondary: #" + &theme.schemes.dark.on_secondary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "secondary_container: #" + &theme.schemes.dark.secondary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_secondary_container: #" + &theme.schemes.dark.on_secondary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "secondary_fixed: #" + &theme.schemes.dark.secondary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "secondary_fixed_dim: #" + &theme.schemes.dark.secondary_fixed_dim.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_secondary_fixed: #" + &theme.schemes.dark.on_secondary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_secondary_fixed_variant: #" + &theme.schemes.dark.on_secondary_fixed_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "tertiary: #" + &theme.schemes.dark.tertiary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_tertiary: #" + &theme.schemes.dark.on_tertiary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "tertiary_container: #" + &theme.schemes.dark.tertiary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_tertiary_container: #" + &theme.schemes.dark.on_tertiary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "tertiary_fixed: #" + &theme.schemes.dark.tertiary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "tertiary_fixed_dim: #" + &theme.schemes.dark.tertiary_fixed_dim.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_tertiary_fixed: #" + &theme.schemes.dark.on_tertiary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_tertiary_fixed_variant: #" + &theme.schemes.dark.on_tertiary_fixed_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "error: #" + &theme.schemes.dark.error.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_error: #" + &theme.schemes.dark.on_error.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "error_container: #" + &theme.schemes.dark.error_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_error_container: #" + &theme.schemes.dark.on_error_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_dim: #" + &theme.schemes.dark.surface_dim.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface: #" + &theme.schemes.dark.surface.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_tint: #" + &theme.schemes.dark.surface_tint.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_bright: #" + &theme.schemes.dark.surface_bright.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_container_lowest: #" + &theme.schemes.dark.surface_container_lowest.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_container_low: #" + &theme.schemes.dark.surface_container_low.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_container: #" + &theme.schemes.dark.surface_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_container_high: #" + &theme.schemes.dark.surface_container_high.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_container_highest: #" + &theme.schemes.dark.surface_container_highest.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_surface: #" + &theme.schemes.dark.on_surface.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_surface_variant: #" + &theme.schemes.dark.on_surface_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "outline: #" + &theme.schemes.dark.outline.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "outline_variant: #" + &theme.schemes.dark.outline_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "inverse_surface: #" + &theme.schemes.dark.inverse_surface.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "inverse_on_surface: #" + &theme.schemes.dark.inverse_on_surface.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_variant: #" + &theme.schemes.dark.surface_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "background: #" + &theme.schemes.dark.background.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_background: #" + &theme.schemes.dark.on_background.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "shadow: #" + &theme.schemes.dark.shadow.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "scrim: #" + &theme.schemes.dark.scrim.to_hex() + ";\n");
-    } else {
-        css_buf += &("  --".to_owned() + "primary: #" + &theme.schemes.light.primary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_primary: #" + &theme.schemes.light.on_primary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "primary_container: #" + &theme.schemes.light.primary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_primary_container: #" + &theme.schemes.light.on_primary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "inverse_primary: #" + &theme.schemes.light.inverse_primary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "primary_fixed: #" + &theme.schemes.light.primary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "primary_fixed_dim: #" + &theme.schemes.light.primary_fixed_dim.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_primary_fixed: #" + &theme.schemes.light.on_primary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_primary_fixed_variant: #" + &theme.schemes.light.on_primary_fixed_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "secondary: #" + &theme.schemes.light.secondary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_secondary: #" + &theme.schemes.light.on_secondary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "secondary_container: #" + &theme.schemes.light.secondary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_secondary_container: #" + &theme.schemes.light.on_secondary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "secondary_fixed: #" + &theme.schemes.light.secondary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "secondary_fixed_dim: #" + &theme.schemes.light.secondary_fixed_dim.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_secondary_fixed: #" + &theme.schemes.light.on_secondary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_secondary_fixed_variant: #" + &theme.schemes.light.on_secondary_fixed_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "tertiary: #" + &theme.schemes.light.tertiary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_tertiary: #" + &theme.schemes.light.on_tertiary.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "tertiary_container: #" + &theme.schemes.light.tertiary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_tertiary_container: #" + &theme.schemes.light.on_tertiary_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "tertiary_fixed: #" + &theme.schemes.light.tertiary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "tertiary_fixed_dim: #" + &theme.schemes.light.tertiary_fixed_dim.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_tertiary_fixed: #" + &theme.schemes.light.on_tertiary_fixed.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_tertiary_fixed_variant: #" + &theme.schemes.light.on_tertiary_fixed_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "error: #" + &theme.schemes.light.error.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_error: #" + &theme.schemes.light.on_error.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "error_container: #" + &theme.schemes.light.error_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_error_container: #" + &theme.schemes.light.on_error_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_dim: #" + &theme.schemes.light.surface_dim.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface: #" + &theme.schemes.light.surface.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_tint: #" + &theme.schemes.light.surface_tint.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_bright: #" + &theme.schemes.light.surface_bright.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_container_lowest: #" + &theme.schemes.light.surface_container_lowest.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_container_low: #" + &theme.schemes.light.surface_container_low.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_container: #" + &theme.schemes.light.surface_container.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_container_high: #" + &theme.schemes.light.surface_container_high.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_container_highest: #" + &theme.schemes.light.surface_container_highest.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_surface: #" + &theme.schemes.light.on_surface.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_surface_variant: #" + &theme.schemes.light.on_surface_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "outline: #" + &theme.schemes.light.outline.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "outline_variant: #" + &theme.schemes.light.outline_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "inverse_surface: #" + &theme.schemes.light.inverse_surface.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "inverse_on_surface: #" + &theme.schemes.light.inverse_on_surface.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "surface_variant: #" + &theme.schemes.light.surface_variant.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "background: #" + &theme.schemes.light.background.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "on_background: #" + &theme.schemes.light.on_background.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "shadow: #" + &theme.schemes.light.shadow.to_hex() + ";\n");
-        css_buf += &("  --".to_owned() + "scrim: #" + &theme.schemes.light.scrim.to_hex() + ";\n");
-    }
-    css_buf += "}";
-    Ok(css_buf)
-}
-fn theme_to_waybar_css(theme: &Theme, is_dark: bool) -> Result<String, ()> {
-    let mut css_buf = String::new();
-
-    if is_dark {
-        css_buf += &("@define-color".to_owned()
-            + " primary #"
-            + &theme.schemes.dark.primary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_primary #"
-            + &theme.schemes.dark.on_primary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " primary_container #"
-            + &theme.schemes.dark.primary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_primary_container #"
-            + &theme.schemes.dark.on_primary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " inverse_primary #"
-            + &theme.schemes.dark.inverse_primary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " primary_fixed #"
-            + &theme.schemes.dark.primary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " primary_fixed_dim #"
-            + &theme.schemes.dark.primary_fixed_dim.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_primary_fixed #"
-            + &theme.schemes.dark.on_primary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_primary_fixed_variant #"
-            + &theme.schemes.dark.on_primary_fixed_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " secondary #"
-            + &theme.schemes.dark.secondary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_secondary #"
-            + &theme.schemes.dark.on_secondary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " secondary_container #"
-            + &theme.schemes.dark.secondary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_secondary_container #"
-            + &theme.schemes.dark.on_secondary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " secondary_fixed #"
-            + &theme.schemes.dark.secondary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " secondary_fixed_dim #"
-            + &theme.schemes.dark.secondary_fixed_dim.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_secondary_fixed #"
-            + &theme.schemes.dark.on_secondary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_secondary_fixed_variant #"
-            + &theme.schemes.dark.on_secondary_fixed_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " tertiary #"
-            + &theme.schemes.dark.tertiary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_tertiary #"
-            + &theme.schemes.dark.on_tertiary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " tertiary_container #"
-            + &theme.schemes.dark.tertiary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_tertiary_container #"
-            + &theme.schemes.dark.on_tertiary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " tertiary_fixed #"
-            + &theme.schemes.dark.tertiary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " tertiary_fixed_dim #"
-            + &theme.schemes.dark.tertiary_fixed_dim.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_tertiary_fixed #"
-            + &theme.schemes.dark.on_tertiary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_tertiary_fixed_variant #"
-            + &theme.schemes.dark.on_tertiary_fixed_variant.to_hex()
-            + ";\n");
-        css_buf +=
-            &("@define-color".to_owned() + " error #" + &theme.schemes.dark.error.to_hex() + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_error #"
-            + &theme.schemes.dark.on_error.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " error_container #"
-            + &theme.schemes.dark.error_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_error_container #"
-            + &theme.schemes.dark.on_error_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_dim #"
-            + &theme.schemes.dark.surface_dim.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface #"
-            + &theme.schemes.dark.surface.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_tint #"
-            + &theme.schemes.dark.surface_tint.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_bright #"
-            + &theme.schemes.dark.surface_bright.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_container_lowest #"
-            + &theme.schemes.dark.surface_container_lowest.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_container_low #"
-            + &theme.schemes.dark.surface_container_low.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_container #"
-            + &theme.schemes.dark.surface_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_container_high #"
-            + &theme.schemes.dark.surface_container_high.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_container_highest #"
-            + &theme.schemes.dark.surface_container_highest.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_surface #"
-            + &theme.schemes.dark.on_surface.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_surface_variant #"
-            + &theme.schemes.dark.on_surface_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " outline #"
-            + &theme.schemes.dark.outline.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " outline_variant #"
-            + &theme.schemes.dark.outline_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " inverse_surface #"
-            + &theme.schemes.dark.inverse_surface.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " inverse_on_surface #"
-            + &theme.schemes.dark.inverse_on_surface.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_variant #"
-            + &theme.schemes.dark.surface_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " background #"
-            + &theme.schemes.dark.background.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_background #"
-            + &theme.schemes.dark.on_background.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " shadow #"
-            + &theme.schemes.dark.shadow.to_hex()
-            + ";\n");
-        css_buf +=
-            &("@define-color".to_owned() + " scrim #" + &theme.schemes.dark.scrim.to_hex() + ";\n");
-    } else {
-        css_buf += &("@define-color".to_owned()
-            + " primary #"
-            + &theme.schemes.light.primary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_primary #"
-            + &theme.schemes.light.on_primary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " primary_container #"
-            + &theme.schemes.light.primary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_primary_container #"
-            + &theme.schemes.light.on_primary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " inverse_primary #"
-            + &theme.schemes.light.inverse_primary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " primary_fixed #"
-            + &theme.schemes.light.primary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " primary_fixed_dim #"
-            + &theme.schemes.light.primary_fixed_dim.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_primary_fixed #"
-            + &theme.schemes.light.on_primary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_primary_fixed_variant #"
-            + &theme.schemes.light.on_primary_fixed_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " secondary #"
-            + &theme.schemes.light.secondary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_secondary #"
-            + &theme.schemes.light.on_secondary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " secondary_container #"
-            + &theme.schemes.light.secondary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_secondary_container #"
-            + &theme.schemes.light.on_secondary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " secondary_fixed #"
-            + &theme.schemes.light.secondary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " secondary_fixed_dim #"
-            + &theme.schemes.light.secondary_fixed_dim.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_secondary_fixed #"
-            + &theme.schemes.light.on_secondary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_secondary_fixed_variant #"
-            + &theme.schemes.light.on_secondary_fixed_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " tertiary #"
-            + &theme.schemes.light.tertiary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_tertiary #"
-            + &theme.schemes.light.on_tertiary.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " tertiary_container #"
-            + &theme.schemes.light.tertiary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_tertiary_container #"
-            + &theme.schemes.light.on_tertiary_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " tertiary_fixed #"
-            + &theme.schemes.light.tertiary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " tertiary_fixed_dim #"
-            + &theme.schemes.light.tertiary_fixed_dim.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_tertiary_fixed #"
-            + &theme.schemes.light.on_tertiary_fixed.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_tertiary_fixed_variant #"
-            + &theme.schemes.light.on_tertiary_fixed_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " error #"
-            + &theme.schemes.light.error.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_error #"
-            + &theme.schemes.light.on_error.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " error_container #"
-            + &theme.schemes.light.error_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_error_container #"
-            + &theme.schemes.light.on_error_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_dim #"
-            + &theme.schemes.light.surface_dim.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface #"
-            + &theme.schemes.light.surface.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_tint #"
-            + &theme.schemes.light.surface_tint.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_bright #"
-            + &theme.schemes.light.surface_bright.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_container_lowest #"
-            + &theme.schemes.light.surface_container_lowest.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_container_low #"
-            + &theme.schemes.light.surface_container_low.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_container #"
-            + &theme.schemes.light.surface_container.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_container_high #"
-            + &theme.schemes.light.surface_container_high.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_container_highest #"
-            + &theme.schemes.light.surface_container_highest.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_surface #"
-            + &theme.schemes.light.on_surface.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_surface_variant #"
-            + &theme.schemes.light.on_surface_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " outline #"
-            + &theme.schemes.light.outline.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " outline_variant #"
-            + &theme.schemes.light.outline_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " inverse_surface #"
-            + &theme.schemes.light.inverse_surface.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " inverse_on_surface #"
-            + &theme.schemes.light.inverse_on_surface.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " surface_variant #"
-            + &theme.schemes.light.surface_variant.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " background #"
-            + &theme.schemes.light.background.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " on_background #"
-            + &theme.schemes.light.on_background.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " shadow #"
-            + &theme.schemes.light.shadow.to_hex()
-            + ";\n");
-        css_buf += &("@define-color".to_owned()
-            + " scrim #"
-            + &theme.schemes.light.scrim.to_hex()
-            + ";\n");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_args() {
+        assert_eq!(
+            parse_image_arg("wall.png"),
+            Ok((PathBuf::from("wall.png"), 1.0))
+        );
+        assert_eq!(
+            parse_image_arg("wall.png:2.5"),
+            Ok((PathBuf::from("wall.png"), 2.5))
+        );
+        // only the last colon separates the weight
+        assert_eq!(
+            parse_image_arg("a:b.png:3"),
+            Ok((PathBuf::from("a:b.png"), 3.0))
+        );
+        assert_eq!(
+            parse_image_arg("12:30.png"),
+            Ok((PathBuf::from("12:30.png"), 1.0))
+        );
+        assert_eq!(
+            parse_image_arg("wall.png:"),
+            Ok((PathBuf::from("wall.png:"), 1.0))
+        );
+
+        for arg in [
+            "wall.png:0",
+            "wall.png:-1",
+            "wall.png:nan",
+            "wall.png:inf",
+            "wall.png:-inf",
+        ] {
+            assert!(parse_image_arg(arg).is_err(), "{arg}");
+        }
     }
-    Ok(css_buf)
 }
@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+
+use material_colors::{
+    color::Argb,
+    dynamic_color::Variant,
+    theme::{Theme, ThemeBuilder},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{Outputs, adjust::Adjustments, custom::Custom, overrides::KeyColors};
+
+// the last theme written, in the state directory
+pub const THEME_FILE: &str = "theme.json";
+
+/// How the theme in `theme.json` came about, written next to the theme
+/// under `metadata`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Metadata {
+    /// mat3 version the theme was generated with.
+    pub version: String,
+    /// Empty for themes not made from images.
+    pub images: Vec<SourceImage>,
+    /// Source color picked from the image (or text) before blending and
+    /// adjusting it, `source` of the theme is the final one.
+    #[serde(with = "crate::hex")]
+    pub seed: Argb,
+    pub adjustments: Adjustments,
+    pub recipe: Recipe,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SourceImage {
+    pub path: PathBuf,
+    /// `hash::file_hash` of the image.
+    pub hash: String,
+    pub weight: f64,
+}
+
+/// Everything the theme is built from. `Theme` can't be read back from
+/// json, so saved themes get rebuilt from this instead.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Recipe {
+    #[serde(with = "crate::hex")]
+    pub source: Argb,
+    #[serde(with = "variant_name")]
+    pub variant: Variant,
+    /// From -1 (reduced) to 1 (high), 0 is the standard contrast.
+    #[serde(default)]
+    pub contrast: f64,
+    pub key_colors: KeyColors,
+    /// Accents included.
+    pub custom_colors: Vec<Custom>,
+}
+
+impl Recipe {
+    pub fn build(&self) -> Theme {
+        let mut theme = ThemeBuilder::with_source(self.source)
+            .variant(self.variant.clone())
+            .custom_colors(
+                self.custom_colors
+                    .iter()
+                    .map(Custom::to_custom_color)
+                    .collect(),
+            )
+            .build();
+
+        self.key_colors
+            .apply(&mut theme, &self.variant, self.contrast);
+        theme
+    }
+}
+
+/// `theme.json`, the theme with its metadata, the scheme that was written
+/// from it and where it was written to.
+#[derive(Serialize)]
+pub struct ThemeFile<'a> {
+    #[serde(flatten)]
+    pub theme: &'a Theme,
+    pub metadata: &'a Metadata,
+    pub is_dark: bool,
+    pub outputs: &'a Outputs,
+}
+
+/// The outputs of `theme.json`, missing in themes from older versions.
+#[derive(Deserialize, Default)]
+pub struct LastOutputs {
+    pub outputs: Option<Outputs>,
+}
+
+/// The parts of `theme.json` that are read back.
+#[derive(Serialize, Deserialize)]
+pub struct SavedTheme {
+    pub metadata: Metadata,
+    pub is_dark: bool,
+}
+
+pub fn variant_to_name(variant: &Variant) -> &'static str {
+    match variant {
+        Variant::Monochrome => "monochrome",
+        Variant::Neutral => "neutral",
+        Variant::TonalSpot => "tonal_spot",
+        Variant::Vibrant => "vibrant",
+        Variant::Expressive => "expressive",
+        Variant::Fidelity => "fidelity",
+        Variant::Content => "content",
+        Variant::Rainbow => "rainbow",
+        Variant::FruitSalad => "fruit_salad",
+    }
+}
+
+pub fn variant_from_name(name: &str) -> Option<Variant> {
+    match name {
+        "monochrome" => Some(Variant::Monochrome),
+        "neutral" => Some(Variant::Neutral),
+        "tonal_spot" => Some(Variant::TonalSpot),
+        "vibrant" => Some(Variant::Vibrant),
+        "expressive" => Some(Variant::Expressive),
+        "fidelity" => Some(Variant::Fidelity),
+        "content" => Some(Variant::Content),
+        "rainbow" => Some(Variant::Rainbow),
+        "fruit_salad" => Some(Variant::FruitSalad),
+        _ => None,
+    }
+}
+
+// variants are written by name, the library doesn't serialize them
+mod variant_name {
+    use material_colors::dynamic_color::Variant;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S>(variant: &Variant, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(super::variant_to_name(variant))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Variant, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        super::variant_from_name(&name)
+            .ok_or_else(|| D::Error::custom(format!("`{name}` is not a variant")))
+    }
+}
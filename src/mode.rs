@@ -0,0 +1,75 @@
+use std::process;
+
+use clap::{Args, ValueEnum};
+use material_colors::{hct::Hct, quantize::QuantizerResult};
+use serde::Deserialize;
+
+use crate::{OutputArgs, apply, metadata, schedule::Schedule, seed, state};
+
+// average tone from which `auto` picks the light scheme
+pub const DEFAULT_AUTO_THRESHOLD: f64 = 50.0;
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemeMode {
+    Light,
+    Dark,
+    /// Light for bright images, dark for dark ones
+    Auto,
+    /// Light during the day, by the [schedule] section of the config
+    Schedule,
+}
+
+/// How the light or dark scheme gets chosen.
+pub struct Options {
+    pub scheme: SchemeMode,
+    pub auto_threshold: f64,
+    // only set for `SchemeMode::Schedule`
+    pub schedule: Option<Schedule>,
+}
+
+/// Whether to write the dark scheme. `image` is the quantized image the
+/// theme is made from, without one `auto` falls back to dark.
+pub fn is_dark(options: &Options, image: Option<&QuantizerResult>) -> bool {
+    match options.scheme {
+        SchemeMode::Light => false,
+        SchemeMode::Dark => true,
+        SchemeMode::Auto => match image {
+            Some(image) => seed::mean(image, Hct::get_tone) < options.auto_threshold,
+            None => true,
+        },
+        SchemeMode::Schedule => options
+            .schedule
+            .is_none_or(|schedule| schedule.is_dark_now()),
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Switch {
+    Light,
+    Dark,
+    /// Light if the outputs are dark and the other way around
+    Toggle,
+}
+
+#[derive(Args, Debug)]
+pub struct ModeArgs {
+    #[arg(value_enum)]
+    mode: Switch,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// Writes the outputs of the last generated theme again in the other
+/// scheme. The theme gets rebuilt from its recipe, the image isn't needed.
+pub fn switch(arguments: ModeArgs) {
+    let outputs = crate::check_outputs(arguments.output);
+
+    let Some(saved) = state::read_json::<Option<metadata::SavedTheme>>(metadata::THEME_FILE) else {
+        println!("there is no theme to switch yet, generate one first");
+        process::exit(1);
+    };
+
+    apply::apply_saved(saved, Some(arguments.mode), &outputs);
+}
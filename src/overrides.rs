@@ -0,0 +1,88 @@
+use material_colors::{
+    color::Argb,
+    dynamic_color::{DynamicScheme, Variant},
+    palette::TonalPalette,
+    scheme::Scheme,
+    theme::{Schemes, Theme},
+};
+use serde::{Deserialize, Serialize};
+
+/// Key colors replacing the ones derived from the source color, for
+/// example a brand red as error color. Palettes without an override still
+/// come from the source color.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyColors {
+    #[serde(with = "crate::hex::option", skip_serializing_if = "Option::is_none")]
+    pub primary: Option<Argb>,
+    #[serde(with = "crate::hex::option", skip_serializing_if = "Option::is_none")]
+    pub secondary: Option<Argb>,
+    #[serde(with = "crate::hex::option", skip_serializing_if = "Option::is_none")]
+    pub tertiary: Option<Argb>,
+    #[serde(with = "crate::hex::option", skip_serializing_if = "Option::is_none")]
+    pub neutral: Option<Argb>,
+    #[serde(with = "crate::hex::option", skip_serializing_if = "Option::is_none")]
+    pub neutral_variant: Option<Argb>,
+    #[serde(with = "crate::hex::option", skip_serializing_if = "Option::is_none")]
+    pub error: Option<Argb>,
+}
+
+impl KeyColors {
+    /// Fills in every color not set here from `other`.
+    pub fn or(self, other: KeyColors) -> KeyColors {
+        KeyColors {
+            primary: self.primary.or(other.primary),
+            secondary: self.secondary.or(other.secondary),
+            tertiary: self.tertiary.or(other.tertiary),
+            neutral: self.neutral.or(other.neutral),
+            neutral_variant: self.neutral_variant.or(other.neutral_variant),
+            error: self.error.or(other.error),
+        }
+    }
+
+    /// Rebuilds the schemes of `theme` at `contrast` with the palettes of the
+    /// key colors set here, and puts those palettes into `theme.palettes` as
+    /// well.
+    ///
+    /// `ThemeBuilder` has setters for this, but they derive the palettes
+    /// like they would from a source color (the tertiary hue gets rotated,
+    /// the error palette is always the default red), so the key colors
+    /// wouldn't end up in the theme.
+    pub fn apply(&self, theme: &mut Theme, variant: &Variant, contrast: f64) {
+        let palette = |color: Option<Argb>| color.map(|color| TonalPalette::from_hct(color.into()));
+
+        let primary = palette(self.primary);
+        let secondary = palette(self.secondary);
+        let tertiary = palette(self.tertiary);
+        let neutral = palette(self.neutral);
+        let neutral_variant = palette(self.neutral_variant);
+        let error = palette(self.error);
+
+        let scheme = |is_dark: bool| {
+            let mut scheme =
+                DynamicScheme::by_variant(theme.source, variant, is_dark, Some(contrast));
+
+            scheme.primary_palette = primary.unwrap_or(scheme.primary_palette);
+            scheme.secondary_palette = secondary.unwrap_or(scheme.secondary_palette);
+            scheme.tertiary_palette = tertiary.unwrap_or(scheme.tertiary_palette);
+            scheme.neutral_palette = neutral.unwrap_or(scheme.neutral_palette);
+            scheme.neutral_variant_palette =
+                neutral_variant.unwrap_or(scheme.neutral_variant_palette);
+            scheme.error_palette = error.unwrap_or(scheme.error_palette);
+
+            Scheme::from(scheme)
+        };
+        theme.schemes = Schemes {
+            light: scheme(false),
+            dark: scheme(true),
+        };
+
+        let palettes = &mut theme.palettes;
+        palettes.primary = primary.unwrap_or(palettes.primary);
+        palettes.secondary = secondary.unwrap_or(palettes.secondary);
+        palettes.tertiary = tertiary.unwrap_or(palettes.tertiary);
+        palettes.neutral = neutral.unwrap_or(palettes.neutral);
+        palettes.neutral_variant = neutral_variant.unwrap_or(palettes.neutral_variant);
+        palettes.error = error.unwrap_or(palettes.error);
+    }
+}
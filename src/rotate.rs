@@ -0,0 +1,193 @@
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+
+use crate::{ThemeArgs, mode, wallpapers};
+
+// how often the wait between two images checks for SIGUSR1
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+static SKIP: AtomicBool = AtomicBool::new(false);
+
+#[derive(Args, Debug)]
+pub struct RotateArgs {
+    /// Directory with the images to cycle through
+    #[arg(short, long, value_name = "DIR")]
+    dir: PathBuf,
+
+    /// How long each image stays, like `90s`, `30m` or `1h30m`
+    #[arg(long, value_name = "DURATION", default_value = "30m")]
+    #[arg(value_parser = parse_duration)]
+    interval: Duration,
+
+    /// Go through the images in random order instead of alphabetically
+    #[arg(long)]
+    shuffle: bool,
+
+    #[command(flatten)]
+    theme: ThemeArgs,
+}
+
+/// Runs until killed. The position in the directory is kept in the state
+/// directory, so a restarted rotation continues where the last one stopped.
+/// With `--scheme schedule` the current theme is written again whenever the
+/// schedule switches between light and dark.
+pub fn rotate(arguments: RotateArgs) {
+    let settings = crate::check_args(arguments.theme);
+    let pick = if arguments.shuffle {
+        wallpapers::Pick::Shuffle
+    } else {
+        wallpapers::Pick::Next
+    };
+
+    listen_for_skip();
+
+    let mut current = None;
+    loop {
+        // a broken image or an empty directory shouldn't end the rotation,
+        // the next step might work again
+        match wallpapers::pick(&arguments.dir, pick) {
+            Ok(image) => {
+                println!("{}", image.display());
+
+                match crate::run(&[(image, 1.0)], &settings) {
+                    Ok(theme) => {
+                        crate::sigusr2_waybar();
+                        current = Some(theme);
+                    }
+                    Err(err) => println!("{err}"),
+                }
+            }
+            Err(err) => println!("{err}"),
+        }
+
+        let mut is_dark = mode::is_dark(&settings.mode_options, None);
+        wait(arguments.interval, || {
+            if settings.mode_options.scheme != mode::SchemeMode::Schedule {
+                return;
+            }
+
+            let now_dark = mode::is_dark(&settings.mode_options, None);
+            if now_dark != is_dark
+                && let Some((theme, metadata)) = &current
+            {
+                crate::write_theme(theme, metadata, now_dark, &settings.outputs);
+                crate::sigusr2_waybar();
+            }
+            is_dark = now_dark;
+        });
+    }
+}
+
+extern "C" fn on_sigusr1(_: libc::c_int) {
+    SKIP.store(true, Ordering::Relaxed);
+}
+
+fn listen_for_skip() {
+    let handler: extern "C" fn(libc::c_int) = on_sigusr1;
+
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+    unsafe {
+        libc::signal(libc::SIGUSR1, handler as libc::sighandler_t);
+    }
+}
+
+// sleeps for `interval`, or until SIGUSR1 arrives, calling `on_poll` in
+// between
+fn wait(interval: Duration, mut on_poll: impl FnMut()) {
+    let start = Instant::now();
+
+    while let Some(left) = interval.checked_sub(start.elapsed()) {
+        if SKIP.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        on_poll();
+
+        thread::sleep(left.min(POLL_INTERVAL));
+    }
+}
+
+// `90`, `90s`, `30m`, `1h30m`, ... plain numbers are seconds, 0 isn't
+// allowed since rotating would never pause
+fn parse_duration(arg: &str) -> Result<Duration, String> {
+    let invalid = || format!("`{arg}` is not a duration, try something like `90s`, `30m` or `1h`");
+
+    let total = match arg.parse::<u64>() {
+        Ok(seconds) => seconds,
+        Err(_) => {
+            let mut total: u64 = 0;
+            let mut number = String::new();
+            for char in arg.chars() {
+                if char.is_ascii_digit() {
+                    number.push(char);
+                    continue;
+                }
+
+                let unit = match char {
+                    's' => 1,
+                    'm' => 60,
+                    'h' => 60 * 60,
+                    'd' => 24 * 60 * 60,
+                    _ => return Err(invalid()),
+                };
+                let value: u64 = number.parse().map_err(|_| invalid())?;
+                total = value
+                    .checked_mul(unit)
+                    .and_then(|seconds| total.checked_add(seconds))
+                    .ok_or_else(invalid)?;
+                number.clear();
+            }
+
+            if !number.is_empty() {
+                return Err(invalid());
+            }
+            total
+        }
+    };
+
+    if total == 0 {
+        return Err(format!(
+            "`{arg}` is too short, the interval has to be at least 1s"
+        ));
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(24 * 60 * 60)));
+
+        for duration in [
+            "",
+            "0",
+            "0s",
+            "0h0m",
+            "1h30",
+            "h",
+            "1x",
+            "-5",
+            "1.5h",
+            " 30m",
+            "99999999999999999999",
+            "99999999999999999999s",
+            "5124095576030432h",
+            "18446744073709551615s1s",
+        ] {
+            assert!(parse_duration(duration).is_err(), "{duration}");
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::{fs, path::PathBuf, process};
+
+use clap::Args;
+
+use crate::{OutputArgs, apply, metadata, mode::Switch, state};
+
+// directory in the state directory the named themes are kept in
+const THEMES_DIR: &str = "themes";
+
+#[derive(Args, Debug)]
+pub struct NameArgs {
+    /// Name of the saved theme
+    name: String,
+}
+
+#[derive(Args, Debug)]
+pub struct LoadArgs {
+    /// Name of the saved theme
+    name: String,
+
+    /// Scheme to write instead of the one the theme was saved with
+    #[arg(short, long, value_enum)]
+    scheme: Option<Switch>,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// Keeps the last generated theme under `name`, replacing an older one with
+/// the same name.
+pub fn save(arguments: NameArgs) {
+    let path = exit_on_err(theme_path(&arguments.name));
+
+    let current = state::state_dir().join(metadata::THEME_FILE);
+    if !current.exists() {
+        println!("there is no theme to save yet, generate one first");
+        process::exit(1);
+    }
+
+    exit_on_err(themes_dir().and_then(|_| {
+        fs::copy(&current, &path)
+            .map(|_| ())
+            .map_err(|err| format!("couldn't save the theme: {err}"))
+    }));
+    println!("saved as `{}`", arguments.name);
+}
+
+pub fn list() {
+    let Ok(entries) = fs::read_dir(state::state_dir().join(THEMES_DIR)) else {
+        return;
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                Some(path.file_stem()?.to_string_lossy().into_owned())
+            } else {
+                None
+            }
+        })
+        .collect();
+    names.sort();
+
+    for name in names {
+        match read(&name) {
+            Ok(saved) => {
+                let recipe = &saved.metadata.recipe;
+                println!(
+                    "{name}  {}  {}",
+                    recipe.source.to_hex_with_pound(),
+                    metadata::variant_to_name(&recipe.variant)
+                );
+            }
+            Err(err) => println!("{name}  {err}"),
+        }
+    }
+}
+
+/// Writes all outputs from the theme saved under `name`.
+pub fn load(arguments: LoadArgs) {
+    let outputs = crate::check_outputs(arguments.output);
+    let saved = exit_on_err(read(&arguments.name));
+
+    apply::apply_saved(saved, arguments.scheme, &outputs);
+}
+
+pub fn delete(arguments: NameArgs) {
+    let path = exit_on_err(theme_path(&arguments.name));
+
+    if !path.exists() {
+        println!("there is no theme `{}`", arguments.name);
+        process::exit(1);
+    }
+    if let Err(err) = fs::remove_file(&path) {
+        println!("couldn't delete `{}`: {err}", arguments.name);
+        process::exit(1);
+    }
+}
+
+pub fn read(name: &str) -> Result<metadata::SavedTheme, String> {
+    let path = theme_path(name)?;
+    let json = fs::read_to_string(&path).map_err(|_| format!("there is no theme `{name}`"))?;
+
+    serde_json::from_str(&json).map_err(|err| format!("`{name}` is broken: {err}"))
+}
+
+fn theme_path(name: &str) -> Result<PathBuf, String> {
+    // names become file names
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!("`{name}` can't be used as name of a theme"));
+    }
+
+    Ok(state::state_dir()
+        .join(THEMES_DIR)
+        .join(name.to_owned() + ".json"))
+}
+
+fn themes_dir() -> Result<(), String> {
+    fs::create_dir_all(state::state_dir().join(THEMES_DIR))
+        .map_err(|err| format!("couldn't create the themes directory: {err}"))
+}
+
+fn exit_on_err<T>(result: Result<T, String>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(err) => {
+            println!("{err}");
+            process::exit(1);
+        }
+    }
+}
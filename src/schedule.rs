@@ -0,0 +1,307 @@
+use std::f64::consts::PI;
+
+use serde::Deserialize;
+
+/// `[schedule]` in the config, used by `scheme = "schedule"`. Either a
+/// location to compute sunrise and sunset for, or fixed clock times.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleConfig {
+    /// Degrees north, negative is south.
+    pub latitude: Option<f64>,
+    /// Degrees east, negative is west.
+    pub longitude: Option<f64>,
+    /// When the light scheme starts, like `"07:00"`.
+    pub light: Option<String>,
+    /// When the dark scheme starts, like `"19:30"`.
+    pub dark: Option<String>,
+}
+
+/// When to switch between light and dark.
+#[derive(Clone, Copy, Debug)]
+pub enum Schedule {
+    /// Light from sunrise to sunset.
+    Sun { latitude: f64, longitude: f64 },
+    /// Light from `light` to `dark`, in minutes after midnight.
+    Clock { light: f64, dark: f64 },
+}
+
+impl Schedule {
+    pub fn from_config(config: ScheduleConfig) -> Result<Schedule, String> {
+        match config {
+            ScheduleConfig {
+                latitude: Some(latitude),
+                longitude: Some(longitude),
+                light: None,
+                dark: None,
+            } => {
+                if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                    return Err("the latitude or longitude of [schedule] is out of range".to_owned());
+                }
+                Ok(Schedule::Sun {
+                    latitude,
+                    longitude,
+                })
+            }
+            ScheduleConfig {
+                latitude: None,
+                longitude: None,
+                light: Some(light),
+                dark: Some(dark),
+            } => Ok(Schedule::Clock {
+                light: parse_clock_time(&light)?,
+                dark: parse_clock_time(&dark)?,
+            }),
+            _ => Err(
+                "`--scheme schedule` needs either `latitude` and `longitude` or `light` and `dark` in the [schedule] section of the config"
+                    .to_owned(),
+            ),
+        }
+    }
+
+    /// Whether it's currently dark by this schedule, in local time.
+    pub fn is_dark_now(&self) -> bool {
+        self.is_dark_at(&LocalTime::now())
+    }
+
+    fn is_dark_at(&self, now: &LocalTime) -> bool {
+        let (light, dark) = match *self {
+            Schedule::Clock { light, dark } => (light, dark),
+            Schedule::Sun {
+                latitude,
+                longitude,
+            } => match sun_times(now.day_of_year, latitude, longitude) {
+                SunTimes::Rises { sunrise, sunset } => (
+                    (sunrise + now.utc_offset).rem_euclid(MINUTES_PER_DAY),
+                    (sunset + now.utc_offset).rem_euclid(MINUTES_PER_DAY),
+                ),
+                SunTimes::AlwaysUp => return false,
+                SunTimes::AlwaysDown => return true,
+            },
+        };
+
+        // light might start before midnight and end after it
+        let is_light = if light <= dark {
+            light <= now.minutes && now.minutes < dark
+        } else {
+            now.minutes >= light || now.minutes < dark
+        };
+        !is_light
+    }
+}
+
+const MINUTES_PER_DAY: f64 = 24.0 * 60.0;
+
+// `HH:MM` in 24 hour time, as minutes after midnight
+fn parse_clock_time(time: &str) -> Result<f64, String> {
+    let invalid = || format!("`{time}` is not a time like 07:30");
+
+    let (hours, minutes) = time.split_once(':').ok_or_else(invalid)?;
+    // `parse` would take signs as well
+    if !(1..=2).contains(&hours.len())
+        || minutes.len() != 2
+        || !(hours.chars().chain(minutes.chars())).all(|char| char.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let hours: u32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 23 || minutes > 59 {
+        return Err(invalid());
+    }
+
+    Ok(f64::from(hours * 60 + minutes))
+}
+
+struct LocalTime {
+    // 1 on the first of January
+    day_of_year: u32,
+    // after midnight
+    minutes: f64,
+    // local time minus UTC, in minutes
+    utc_offset: f64,
+}
+
+impl LocalTime {
+    fn now() -> LocalTime {
+        // SAFETY: `localtime_r` only writes to the `tm` it's given, which is
+        // fully initialized before it's read
+        let tm = unsafe {
+            let time = libc::time(std::ptr::null_mut());
+            let mut tm: libc::tm = std::mem::zeroed();
+            libc::localtime_r(&time, &mut tm);
+            tm
+        };
+
+        LocalTime {
+            day_of_year: tm.tm_yday as u32 + 1,
+            minutes: f64::from(tm.tm_hour * 60 + tm.tm_min) + f64::from(tm.tm_sec) / 60.0,
+            utc_offset: tm.tm_gmtoff as f64 / 60.0,
+        }
+    }
+}
+
+enum SunTimes {
+    // in minutes after midnight UTC
+    Rises { sunrise: f64, sunset: f64 },
+    // polar day
+    AlwaysUp,
+    // polar night
+    AlwaysDown,
+}
+
+// NOAA's approximation of the sun's position, accurate to a few minutes
+// https://gml.noaa.gov/grad/solcalc/solareqns.PDF
+fn sun_times(day_of_year: u32, latitude: f64, longitude: f64) -> SunTimes {
+    let gamma = 2.0 * PI / 365.0 * f64::from(day_of_year - 1);
+
+    let equation_of_time = 229.18
+        * (0.000075 + 0.001868 * gamma.cos()
+            - 0.032077 * gamma.sin()
+            - 0.014615 * (2.0 * gamma).cos()
+            - 0.040849 * (2.0 * gamma).sin());
+    let declination = 0.006918 - 0.399912 * gamma.cos() + 0.070257 * gamma.sin()
+        - 0.006758 * (2.0 * gamma).cos()
+        + 0.000907 * (2.0 * gamma).sin()
+        - 0.002697 * (3.0 * gamma).cos()
+        + 0.00148 * (3.0 * gamma).sin();
+
+    // 90.833° accounts for refraction and the size of the sun's disk
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = 90.833_f64.to_radians().cos() / (latitude.cos() * declination.cos())
+        - latitude.tan() * declination.tan();
+
+    if cos_hour_angle > 1.0 {
+        return SunTimes::AlwaysDown;
+    }
+    if cos_hour_angle < -1.0 {
+        return SunTimes::AlwaysUp;
+    }
+
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+    SunTimes::Rises {
+        sunrise: 720.0 - 4.0 * (longitude + hour_angle) - equation_of_time,
+        sunset: 720.0 - 4.0 * (longitude - hour_angle) - equation_of_time,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONDON: (f64, f64) = (51.5074, -0.1278);
+    const NEW_YORK: (f64, f64) = (40.7128, -74.006);
+    const TROMSO: (f64, f64) = (69.6492, 18.9553);
+    // June 21st and December 21st
+    const SUMMER: u32 = 172;
+    const WINTER: u32 = 355;
+
+    // published times are rounded to the minute, NOAA's approximation is
+    // off by a few more
+    fn assert_near(minutes: f64, expected: f64) {
+        assert!(
+            (minutes - expected).abs() <= 5.0,
+            "{minutes} is not close to {expected}"
+        );
+    }
+
+    fn sun_times_at((latitude, longitude): (f64, f64), day_of_year: u32) -> SunTimes {
+        sun_times(day_of_year, latitude, longitude)
+    }
+
+    #[test]
+    fn london_in_june() {
+        // 04:43 and 21:21 BST
+        let SunTimes::Rises { sunrise, sunset } = sun_times_at(LONDON, SUMMER) else {
+            panic!("the sun rises in London");
+        };
+        assert_near(sunrise, 3.0 * 60.0 + 43.0);
+        assert_near(sunset, 20.0 * 60.0 + 21.0);
+    }
+
+    #[test]
+    fn new_york_in_december() {
+        // 07:16 and 16:32 EST
+        let SunTimes::Rises { sunrise, sunset } = sun_times_at(NEW_YORK, WINTER) else {
+            panic!("the sun rises in New York");
+        };
+        assert_near(sunrise, 12.0 * 60.0 + 16.0);
+        assert_near(sunset, 21.0 * 60.0 + 32.0);
+    }
+
+    #[test]
+    fn polar_day_and_night() {
+        assert!(matches!(sun_times_at(TROMSO, SUMMER), SunTimes::AlwaysUp));
+        assert!(matches!(sun_times_at(TROMSO, WINTER), SunTimes::AlwaysDown));
+
+        let (latitude, longitude) = TROMSO;
+        let schedule = Schedule::Sun {
+            latitude,
+            longitude,
+        };
+        let at = |day_of_year| LocalTime {
+            day_of_year,
+            minutes: 0.0,
+            utc_offset: 60.0,
+        };
+        assert!(!schedule.is_dark_at(&at(SUMMER)));
+        assert!(schedule.is_dark_at(&at(WINTER)));
+    }
+
+    #[test]
+    fn sun_in_local_time() {
+        let (latitude, longitude) = LONDON;
+        let schedule = Schedule::Sun {
+            latitude,
+            longitude,
+        };
+        // BST
+        let at = |minutes| LocalTime {
+            day_of_year: SUMMER,
+            minutes,
+            utc_offset: 60.0,
+        };
+
+        assert!(schedule.is_dark_at(&at(4.0 * 60.0 + 30.0)));
+        assert!(!schedule.is_dark_at(&at(5.0 * 60.0)));
+        assert!(!schedule.is_dark_at(&at(21.0 * 60.0)));
+        assert!(schedule.is_dark_at(&at(21.0 * 60.0 + 40.0)));
+    }
+
+    #[test]
+    fn clock_across_midnight() {
+        // night shift, light from 22:00 to 06:00
+        let schedule = Schedule::Clock {
+            light: 22.0 * 60.0,
+            dark: 6.0 * 60.0,
+        };
+        let at = |minutes| LocalTime {
+            day_of_year: 1,
+            minutes,
+            utc_offset: 0.0,
+        };
+
+        assert!(!schedule.is_dark_at(&at(23.0 * 60.0)));
+        assert!(!schedule.is_dark_at(&at(0.0)));
+        assert!(!schedule.is_dark_at(&at(5.0 * 60.0 + 59.0)));
+        assert!(schedule.is_dark_at(&at(6.0 * 60.0)));
+        assert!(schedule.is_dark_at(&at(12.0 * 60.0)));
+        assert!(schedule.is_dark_at(&at(21.0 * 60.0 + 59.0)));
+        assert!(!schedule.is_dark_at(&at(22.0 * 60.0)));
+    }
+
+    #[test]
+    fn clock_times() {
+        assert_eq!(parse_clock_time("07:30"), Ok(450.0));
+        assert_eq!(parse_clock_time("7:30"), Ok(450.0));
+        assert_eq!(parse_clock_time("00:00"), Ok(0.0));
+        assert_eq!(parse_clock_time("23:59"), Ok(1439.0));
+
+        for time in [
+            "", "7", "07:", ":30", "24:00", "12:60", "7:5", "ab:cd", "+7:30", "07:-1", "007:30",
+            "07:30:00", " 07:30",
+        ] {
+            assert!(parse_clock_time(time).is_err(), "{time}");
+        }
+    }
+}
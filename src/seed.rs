@@ -0,0 +1,225 @@
+use clap::ValueEnum;
+use material_colors::{
+    color::Argb, dynamic_color::Variant, hct::Hct, quantize::QuantizerResult, score::Score,
+};
+use serde::Deserialize;
+
+use crate::hash;
+
+pub const DEFAULT_FALLBACK_SEED: Argb = Argb::new(255, 66, 133, 244);
+pub const DEFAULT_LOW_CHROMA_THRESHOLD: f64 = 8.0;
+// chroma and tone of seeds made from text, the hue comes from the hash
+const TEXT_SEED_CHROMA: f64 = 48.0;
+const TEXT_SEED_TONE: f64 = 60.0;
+// how many ranked colors the preferred hues get looked for in
+const CANDIDATES: i32 = 16;
+// accents closer than this to the hue of the source color look the same
+const MIN_ACCENT_HUE_DISTANCE: f64 = 15.0;
+// what `Score` returns when no color of the image is good enough
+const SCORE_FALLBACK: Argb = Argb::new(255, 66, 133, 244);
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LowChroma {
+    /// Use the fallback seed instead of the image's color
+    Fallback,
+    /// Keep the image's color but switch to the monochrome variant
+    Monochrome,
+    /// Keep the image's color but switch to the neutral variant
+    Neutral,
+    /// Only print a warning
+    Warn,
+}
+
+/// How the source color gets picked out of the quantized image.
+pub struct Options {
+    pub low_chroma: LowChroma,
+    pub low_chroma_threshold: f64,
+    pub fallback_seed: Argb,
+    pub exclude_hues: Vec<[f64; 2]>,
+    pub prefer_hues: Vec<[f64; 2]>,
+    pub min_chroma: f64,
+}
+
+// a chroma of 0 or more, for the low chroma threshold
+pub fn parse_chroma(arg: &str) -> Result<f64, String> {
+    match arg.parse::<f64>() {
+        Ok(chroma) if chroma >= 0.0 && chroma.is_finite() => Ok(chroma),
+        _ => Err(format!("`{arg}` is not a chroma of 0 or more")),
+    }
+}
+
+/// What `choose` picked.
+pub struct Choice {
+    pub seed: Argb,
+    /// Only differs from the requested variant when the image is (nearly)
+    /// grayscale.
+    pub variant: Variant,
+    /// Problems with the image worth telling the user about.
+    pub warnings: Vec<String>,
+}
+
+/// Scores the quantized image and returns the source color along with the
+/// variant to build the theme with.
+pub fn choose(result: &QuantizerResult, variant: &Variant, options: &Options) -> Choice {
+    let mut warnings = Vec::new();
+    let seed = filtered_seed(result, options, &mut warnings);
+
+    let mut choice = Choice {
+        seed,
+        variant: variant.clone(),
+        warnings,
+    };
+
+    let chroma = mean(result, Hct::get_chroma);
+    if chroma >= options.low_chroma_threshold {
+        return choice;
+    }
+
+    match options.low_chroma {
+        LowChroma::Fallback => {
+            choice.warnings.push(format!(
+                "the image is almost grayscale (average chroma {chroma:.1}), using the fallback seed"
+            ));
+            choice.seed = options.fallback_seed;
+        }
+        LowChroma::Monochrome => choice.variant = Variant::Monochrome,
+        LowChroma::Neutral => choice.variant = Variant::Neutral,
+        LowChroma::Warn => choice.warnings.push(format!(
+            "the image is almost grayscale (average chroma {chroma:.1}), the theme might look muddy"
+        )),
+    }
+
+    choice
+}
+
+/// Picks the best scored color that passes the hue and chroma filters,
+/// preferring the ones in `prefer_hues`.
+fn filtered_seed(result: &QuantizerResult, options: &Options, warnings: &mut Vec<String>) -> Argb {
+    let filtered = filtered(result, options);
+
+    if filtered.color_to_count.is_empty() {
+        warnings
+            .push("every color of the image is excluded by the hue and chroma filters".to_owned());
+        return score(result, 1)[0];
+    }
+
+    let candidates = score(&filtered, CANDIDATES);
+
+    *candidates
+        .iter()
+        .find(|color| {
+            let hue = Hct::new(**color).get_hue();
+            options
+                .prefer_hues
+                .iter()
+                .any(|range| in_hue_range(hue, *range))
+        })
+        .unwrap_or(&candidates[0])
+}
+
+/// A source color that is always the same for the same text, like a host
+/// name. Only the hue depends on the text, so every seed works equally well.
+pub fn from_text(text: &str) -> Argb {
+    let hash = hash::fnv1a(text.as_bytes());
+
+    let hue = (hash % 3600) as f64 / 10.0;
+    Hct::from(hue, TEXT_SEED_CHROMA, TEXT_SEED_TONE).into()
+}
+
+/// Up to `count` well-separated colors of the image, best first, to be
+/// used as extra accents next to `seed`. They go through the same hue and
+/// chroma filters as the source color, and images without good colors
+/// get none.
+pub fn accents(result: &QuantizerResult, seed: Argb, count: usize, options: &Options) -> Vec<Argb> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let seed_hue = Hct::new(seed).get_hue();
+    // one more, the source color is usually among them
+    let scored = Score::score(
+        &filtered(result, options).color_to_count,
+        Some(count as i32 + 1),
+        None,
+        None,
+    );
+    if is_score_fallback(&scored, result) {
+        return Vec::new();
+    }
+
+    scored
+        .into_iter()
+        .filter(|color| {
+            hue_distance(Hct::new(*color).get_hue(), seed_hue) >= MIN_ACCENT_HUE_DISTANCE
+        })
+        .take(count)
+        .collect()
+}
+
+// `Score` skips colors with hardly any chroma and falls back to a blue that
+// isn't in the image when nothing is left, so grayscale images get scored
+// again without skipping any to keep their own color
+fn score(result: &QuantizerResult, desired: i32) -> Vec<Argb> {
+    let scored = Score::score(&result.color_to_count, Some(desired), None, None);
+    if !is_score_fallback(&scored, result) {
+        return scored;
+    }
+
+    Score::score(&result.color_to_count, Some(desired), None, Some(false))
+}
+
+fn is_score_fallback(scored: &[Argb], result: &QuantizerResult) -> bool {
+    scored == [SCORE_FALLBACK] && !result.color_to_count.contains_key(&SCORE_FALLBACK)
+}
+
+// the colors of the image that pass the hue and chroma filters
+fn filtered(result: &QuantizerResult, options: &Options) -> QuantizerResult {
+    let mut filtered = QuantizerResult {
+        color_to_count: result.color_to_count.clone(),
+        input_pixel_to_cluster_pixel: Default::default(),
+    };
+    filtered.color_to_count.retain(|color, _| {
+        let hct = Hct::new(*color);
+
+        hct.get_chroma() >= options.min_chroma
+            && !options
+                .exclude_hues
+                .iter()
+                .any(|range| in_hue_range(hct.get_hue(), *range))
+    });
+
+    filtered
+}
+
+fn hue_distance(a: f64, b: f64) -> f64 {
+    let distance = (a - b).rem_euclid(360.0);
+    distance.min(360.0 - distance)
+}
+
+// `range` goes clockwise from its first to its second hue
+fn in_hue_range(hue: f64, [start, end]: [f64; 2]) -> bool {
+    if start <= end {
+        start <= hue && hue <= end
+    } else {
+        hue >= start || hue <= end
+    }
+}
+
+/// Average of `property` over the image, like `Hct::get_chroma`, weighted
+/// by how many pixels each cluster stands for.
+pub fn mean(result: &QuantizerResult, property: fn(&Hct) -> f64) -> f64 {
+    let mut sum = 0.0;
+    let mut count_sum = 0.0;
+
+    for (color, count) in &result.color_to_count {
+        sum += property(&Hct::new(*color)) * f64::from(*count);
+        count_sum += f64::from(*count);
+    }
+
+    if count_sum == 0.0 {
+        0.0
+    } else {
+        sum / count_sum
+    }
+}
@@ -0,0 +1,397 @@
+//! Theme codes, the recipe of a theme packed into a short string that can be
+//! pasted into chat and applied on another machine without the image.
+//!
+//! The code is url-safe base64 without padding of:
+//! - format version, 1 byte
+//! - source color, 3 bytes of rgb
+//! - variant, 1 byte
+//! - flags, 1 byte: bit 0 for a contrast other than 0, bits 1 to 6 for the
+//!   key colors in the order of `KEY_COLORS`, bit 7 for dark
+//! - contrast, 8 bytes of the f64 if set
+//! - key colors, 3 bytes each if set
+//! - number of custom colors, 1 byte, so a code cut off between two of them
+//!   isn't taken as a shorter one
+//! - custom colors, each 3 bytes of color, 1 byte of name length shifted left
+//!   once with blend in bit 0, and the name
+
+use std::process;
+
+use clap::Args;
+use material_colors::color::Argb;
+
+use crate::{
+    adjust::Adjustments,
+    apply,
+    custom::{self, Custom},
+    metadata::{self, Metadata, Recipe, SavedTheme},
+    overrides::KeyColors,
+    state,
+};
+
+const VERSION: u8 = 1;
+
+// indices of the variants in codes, only ever append to this
+const VARIANTS: [&str; 9] = [
+    "monochrome",
+    "neutral",
+    "tonal_spot",
+    "vibrant",
+    "expressive",
+    "fidelity",
+    "content",
+    "rainbow",
+    "fruit_salad",
+];
+
+const KEY_COLORS: usize = 6;
+const CONTRAST_FLAG: u8 = 1;
+const DARK_FLAG: u8 = 1 << 7;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Args, Debug)]
+pub struct ShareArgs {
+    /// Share this saved theme instead of the last generated one
+    name: Option<String>,
+}
+
+/// Prints the code of the last generated theme, or of a saved one.
+pub fn share(arguments: ShareArgs) {
+    let saved = match arguments.name {
+        Some(name) => crate::saved::read(&name),
+        None => {
+            let current = state::state_dir().join(metadata::THEME_FILE);
+            if !current.exists() {
+                println!("there is no theme to share yet, generate one first");
+                process::exit(1);
+            }
+            apply::read_theme(&current)
+        }
+    };
+
+    match saved.and_then(|saved| encode(&saved.metadata.recipe, saved.is_dark)) {
+        Ok(code) => println!("{code}"),
+        Err(err) => {
+            println!("{err}");
+            process::exit(1);
+        }
+    }
+}
+
+pub fn encode(recipe: &Recipe, is_dark: bool) -> Result<String, String> {
+    let key_colors = key_colors(&recipe.key_colors);
+
+    let variant_name = metadata::variant_to_name(&recipe.variant);
+    let variant = VARIANTS
+        .iter()
+        .position(|name| *name == variant_name)
+        .unwrap();
+
+    let mut flags = if is_dark { DARK_FLAG } else { 0 };
+    if recipe.contrast != 0.0 {
+        flags |= CONTRAST_FLAG;
+    }
+    for (i, color) in key_colors.iter().enumerate() {
+        if color.is_some() {
+            flags |= 1 << (i + 1);
+        }
+    }
+
+    let mut bytes = vec![VERSION];
+    push_color(&mut bytes, recipe.source);
+    bytes.push(variant as u8);
+    bytes.push(flags);
+    if recipe.contrast != 0.0 {
+        bytes.extend(recipe.contrast.to_be_bytes());
+    }
+    for color in key_colors.into_iter().flatten() {
+        push_color(&mut bytes, color);
+    }
+    if recipe.custom_colors.len() > 255 {
+        return Err("there are too many custom colors to share".to_owned());
+    }
+    bytes.push(recipe.custom_colors.len() as u8);
+    for custom in &recipe.custom_colors {
+        let name = custom.name.as_bytes();
+        if name.len() > 127 {
+            return Err(format!(
+                "the name of `{}` is too long to share",
+                custom.name
+            ));
+        }
+
+        push_color(&mut bytes, custom.value);
+        bytes.push((name.len() as u8) << 1 | custom.blend as u8);
+        bytes.extend(name);
+    }
+
+    Ok(base64_encode(&bytes))
+}
+
+/// The theme a code was made from, with metadata like a theme generated
+/// without an image.
+pub fn decode(code: &str) -> Result<SavedTheme, String> {
+    let invalid = || format!("`{code}` is not a theme code");
+
+    let bytes = base64_decode(code.trim()).ok_or_else(invalid)?;
+    let mut reader = Reader { bytes: &bytes };
+
+    let version = reader.byte().ok_or_else(invalid)?;
+    if version != VERSION {
+        return Err(format!(
+            "the code is from another version of mat3 (format {version}, not {VERSION})"
+        ));
+    }
+
+    let source = reader.color().ok_or_else(invalid)?;
+    let variant = reader
+        .byte()
+        .and_then(|index| VARIANTS.get(index as usize))
+        .and_then(|name| metadata::variant_from_name(name))
+        .ok_or_else(invalid)?;
+    let flags = reader.byte().ok_or_else(invalid)?;
+
+    let contrast = if flags & CONTRAST_FLAG != 0 {
+        let contrast = f64::from_be_bytes(reader.take(8).ok_or_else(invalid)?.try_into().unwrap());
+        if !(-1.0..=1.0).contains(&contrast) {
+            return Err(invalid());
+        }
+        contrast
+    } else {
+        0.0
+    };
+
+    let mut key_colors = [None; KEY_COLORS];
+    for (i, color) in key_colors.iter_mut().enumerate() {
+        if flags & 1 << (i + 1) != 0 {
+            *color = Some(reader.color().ok_or_else(invalid)?);
+        }
+    }
+
+    let count = reader.byte().ok_or_else(invalid)?;
+    let mut custom_colors = Vec::new();
+    for _ in 0..count {
+        let value = reader.color().ok_or_else(invalid)?;
+        let length = reader.byte().ok_or_else(invalid)?;
+        let name = reader.take((length >> 1) as usize).ok_or_else(invalid)?;
+        let name = String::from_utf8(name.to_vec()).map_err(|_| invalid())?;
+        // codes come from chat, the names go into the outputs unchanged
+        custom::check_name(&name).map_err(|err| format!("the code is broken: {err}"))?;
+
+        custom_colors.push(Custom {
+            name,
+            value,
+            blend: length & 1 != 0,
+        });
+    }
+
+    if !reader.bytes.is_empty() {
+        return Err(invalid());
+    }
+    // accents are among the custom colors already
+    custom::check_clashes(&custom_colors, 0).map_err(|err| format!("the code is broken: {err}"))?;
+
+    Ok(SavedTheme {
+        metadata: Metadata {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            images: Vec::new(),
+            seed: source,
+            adjustments: Adjustments {
+                hue_shift: 0.0,
+                chroma_scale: 1.0,
+                tone: None,
+            },
+            recipe: Recipe {
+                source,
+                variant,
+                contrast,
+                key_colors: KeyColors {
+                    primary: key_colors[0],
+                    secondary: key_colors[1],
+                    tertiary: key_colors[2],
+                    neutral: key_colors[3],
+                    neutral_variant: key_colors[4],
+                    error: key_colors[5],
+                },
+                custom_colors,
+            },
+        },
+        is_dark: flags & DARK_FLAG != 0,
+    })
+}
+
+fn key_colors(key_colors: &KeyColors) -> [Option<Argb>; KEY_COLORS] {
+    [
+        key_colors.primary,
+        key_colors.secondary,
+        key_colors.tertiary,
+        key_colors.neutral,
+        key_colors.neutral_variant,
+        key_colors.error,
+    ]
+}
+
+fn push_color(bytes: &mut Vec<u8>, color: Argb) {
+    bytes.extend([color.red, color.green, color.blue]);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Option<&[u8]> {
+        if self.bytes.len() < count {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn color(&mut self) -> Option<Argb> {
+        let rgb = self.take(3)?;
+        Some(Argb::new(255, rgb[0], rgb[1], rgb[2]))
+    }
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut code = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from(group[0]) << 16 | u32::from(group[1]) << 8 | u32::from(group[2]);
+
+        // 2 characters for 1 byte, 3 for 2 and 4 for 3
+        for i in 0..=chunk.len() {
+            code.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+
+    code
+}
+
+fn base64_decode(code: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    for chunk in code.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+
+        let mut bits = 0;
+        for (i, character) in chunk.iter().enumerate() {
+            let value = ALPHABET.iter().position(|c| c == character)? as u32;
+            bits |= value << (18 - 6 * i);
+        }
+        bytes.extend(&bits.to_be_bytes()[1..chunk.len()]);
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use material_colors::dynamic_color::Variant;
+
+    use super::*;
+
+    fn recipe() -> Recipe {
+        Recipe {
+            source: Argb::new(255, 0x14, 0x5e, 0xc8),
+            variant: Variant::Vibrant,
+            contrast: 0.37,
+            key_colors: KeyColors {
+                tertiary: Some(Argb::new(255, 0x00, 0xaa, 0x99)),
+                error: Some(Argb::new(255, 0xff, 0x00, 0x00)),
+                ..Default::default()
+            },
+            custom_colors: vec![
+                Custom {
+                    name: "brand".to_owned(),
+                    value: Argb::new(255, 0x12, 0x34, 0x56),
+                    blend: true,
+                },
+                Custom {
+                    name: "warn".to_owned(),
+                    value: Argb::new(255, 0xff, 0xaa, 0x00),
+                    blend: false,
+                },
+            ],
+        }
+    }
+
+    // the bytes of a code up to the flags, for tonal spot and light
+    fn header(flags: u8) -> Vec<u8> {
+        vec![VERSION, 0x14, 0x5e, 0xc8, 2, flags]
+    }
+
+    #[test]
+    fn round_trip() {
+        let code = encode(&recipe(), true).unwrap();
+        let saved = decode(&code).unwrap();
+        let decoded = &saved.metadata.recipe;
+
+        assert!(saved.is_dark);
+        assert_eq!(decoded.source, recipe().source);
+        assert_eq!(metadata::variant_to_name(&decoded.variant), "vibrant");
+        assert_eq!(decoded.contrast, 0.37);
+        assert_eq!(decoded.key_colors.primary, None);
+        assert_eq!(decoded.key_colors.tertiary, recipe().key_colors.tertiary);
+        assert_eq!(decoded.key_colors.error, recipe().key_colors.error);
+        assert_eq!(decoded.custom_colors.len(), 2);
+        assert_eq!(decoded.custom_colors[1].name, "warn");
+        assert_eq!(
+            decoded.custom_colors[1].value,
+            recipe().custom_colors[1].value
+        );
+        assert!(!decoded.custom_colors[1].blend);
+        assert_eq!(encode(decoded, saved.is_dark).unwrap(), code);
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        for length in 0..10 {
+            let bytes: Vec<u8> = (0..length).map(|i: u8| i.wrapping_mul(97)).collect();
+            assert_eq!(base64_decode(&base64_encode(&bytes)).unwrap(), bytes);
+        }
+        assert!(base64_decode("A").is_none());
+        assert!(base64_decode("AA=A").is_none());
+    }
+
+    #[test]
+    fn truncated() {
+        let code = encode(&recipe(), false).unwrap();
+
+        for length in 0..code.len() {
+            assert!(decode(&code[..length]).is_err(), "{}", &code[..length]);
+        }
+    }
+
+    #[test]
+    fn bad_contrast() {
+        for contrast in [2.0, -1.5, f64::NAN, f64::INFINITY] {
+            let mut bytes = header(CONTRAST_FLAG);
+            bytes.extend(f64::to_be_bytes(contrast));
+            bytes.push(0);
+
+            assert!(decode(&base64_encode(&bytes)).is_err());
+        }
+    }
+
+    #[test]
+    fn invalid_name() {
+        for name in ["x: red; } body { background: red } :root { --y", "", "a.b"] {
+            let mut bytes = header(0);
+            bytes.extend([1, 0x12, 0x34, 0x56, (name.len() as u8) << 1 | 1]);
+            bytes.extend(name.as_bytes());
+
+            assert!(decode(&base64_encode(&bytes)).is_err(), "{name}");
+        }
+    }
+}
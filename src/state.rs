@@ -0,0 +1,30 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Directory for everything mat3 remembers between runs,
+/// `$XDG_STATE_HOME/mat3` or `~/.local/state/mat3`.
+pub fn state_dir() -> PathBuf {
+    let dir = crate::xdg_dir("XDG_STATE_HOME", ".local/state").join("mat3");
+
+    fs::create_dir_all(&dir).expect("couldn't create the state directory");
+    dir
+}
+
+/// Reads `name` from the state directory, falling back to the default if
+/// it doesn't exist yet or can't be parsed.
+pub fn read_json<T: DeserializeOwned + Default>(name: &str) -> T {
+    fs::read_to_string(state_dir().join(name))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn write_json<T: Serialize>(name: &str, value: &T) {
+    let json = serde_json::to_string_pretty(value).unwrap();
+    write_file(name, &json);
+}
+
+pub fn write_file(name: &str, contents: &str) {
+    fs::write(state_dir().join(name), contents).expect("couldn't write to the state directory");
+}
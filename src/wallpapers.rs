@@ -0,0 +1,120 @@
+use std::{
+    collections::{HashMap, hash_map::RandomState},
+    fs,
+    hash::{BuildHasher, Hasher},
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+
+use crate::{loader, state};
+
+// file name of the last image picked from each directory
+const SEQUENCE_FILE: &str = "sequence.json";
+// images of each directory not yet used by the current shuffle round
+const SHUFFLE_FILE: &str = "shuffle.json";
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Pick {
+    /// Any image, except the one picked last time
+    Random,
+    /// The image after the one picked last time, in alphabetical order
+    Next,
+    /// Every image once in random order before any of them repeats
+    Shuffle,
+}
+
+/// Every supported image directly inside `dir`, sorted by path.
+pub fn list_images(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|err| format!("couldn't read {}: {err}", dir.display()))?;
+
+    let mut images: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && loader::is_supported(path))
+        .collect();
+    images.sort();
+
+    if images.is_empty() {
+        Err(format!("there are no images in {}", dir.display()))
+    } else {
+        Ok(images)
+    }
+}
+
+/// Picks one image out of `dir` and remembers it for the next call.
+pub fn pick(dir: &Path, pick: Pick) -> Result<PathBuf, String> {
+    let images = list_images(dir)?;
+    let dir_key = dir.canonicalize().unwrap_or(dir.to_path_buf());
+
+    let mut sequence: HashMap<PathBuf, PathBuf> = state::read_json(SEQUENCE_FILE);
+    let last = sequence.get(&dir_key).map(|last| dir.join(last));
+
+    let picked = match pick {
+        Pick::Next => {
+            // the last image might have been deleted since, so look for the
+            // first one sorting after it instead of its position
+            let next = last.and_then(|last| images.iter().find(|image| **image > last));
+            next.unwrap_or(&images[0]).clone()
+        }
+        Pick::Random => {
+            let candidates: Vec<&PathBuf> = images
+                .iter()
+                .filter(|image| images.len() == 1 || Some(*image) != last.as_ref())
+                .collect();
+            candidates[random_index(candidates.len())].clone()
+        }
+        Pick::Shuffle => next_shuffled(dir, &dir_key, &images, last.as_ref()),
+    };
+
+    if let Some(file_name) = picked.file_name() {
+        sequence.insert(dir_key, PathBuf::from(file_name));
+    }
+    state::write_json(SEQUENCE_FILE, &sequence);
+
+    Ok(picked)
+}
+
+fn next_shuffled(
+    dir: &Path,
+    dir_key: &Path,
+    images: &[PathBuf],
+    last: Option<&PathBuf>,
+) -> PathBuf {
+    let mut rounds: HashMap<PathBuf, Vec<PathBuf>> = state::read_json(SHUFFLE_FILE);
+    let round = rounds.entry(dir_key.to_path_buf()).or_default();
+
+    // drop images that were deleted since the round started
+    round.retain(|name| images.contains(&dir.join(name)));
+
+    if round.is_empty() {
+        let mut names: Vec<PathBuf> = images
+            .iter()
+            .filter_map(|image| image.file_name().map(PathBuf::from))
+            .collect();
+        shuffle(&mut names);
+
+        // don't show the same image twice in a row across rounds
+        if names.len() > 1 && last.is_some_and(|last| dir.join(&names[0]) == *last) {
+            names.swap(0, 1);
+        }
+        *round = names;
+    }
+
+    let picked = dir.join(round.remove(0));
+    state::write_json(SHUFFLE_FILE, &rounds);
+
+    picked
+}
+
+fn shuffle<T>(items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, random_index(i + 1));
+    }
+}
+
+// std has no random number generator, but its hash maps are seeded randomly
+pub fn random_index(len: usize) -> usize {
+    let random = RandomState::new().build_hasher().finish();
+    (random % len as u64) as usize
+}